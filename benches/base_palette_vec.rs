@@ -16,25 +16,25 @@ pub fn rng_routine<const THRESHOLD: usize>(unique_values: u32, iterations: u32)
 
     for _ in 0..iterations {
         if rng.random_bool(0.008) {
-            black_box(vec.optimize());
+            vec.optimize();
         }
         if rng.random_bool(0.2) {
             let n = rng.random_range(0..unique_values);
-            black_box(vec.push(n));
+            vec.push(n);
         }
         if rng.random_bool(0.2) {
             let n = rng.random_range(0..unique_values);
-            black_box(vec.push_ref(&n));
+            vec.push_ref(&n);
         }
         if rng.random_bool(0.33) {
             black_box(vec.pop());
         }
-        if rng.random_bool(0.5) && vec.len() > 0 {
+        if rng.random_bool(0.5) && !vec.is_empty() {
             let index = rng.random_range(0..vec.len());
             let n = rng.random_range(0..unique_values);
-            black_box(vec.set(index, &n));
+            vec.set(index, &n);
         }
-        if rng.random_bool(0.5) && vec.len() > 0 {
+        if rng.random_bool(0.5) && !vec.is_empty() {
             let index = rng.random_range(0..vec.len());
             black_box(vec.get(index));
         }
@@ -199,8 +199,8 @@ fn benchmark_set_for_threshold<const THRESHOLD: usize>(
                     |pv_ref| {
                         for i in 0..get_iters {
                             let index = i as usize % pv_ref.len();
-                            let value = i as u32 % uv;
-                            black_box(pv_ref.set(index, &value));
+                            let value = i % uv;
+                            pv_ref.set(index, &value);
                         }
                     },
                     BatchSize::SmallInput,
@@ -0,0 +1,132 @@
+//! A serde representation of `PaletteVec` that doesn't depend on the `Palette`
+//! and `IndexBuffer` implementations, so data can be stored with one backend
+//! and loaded into another.
+//!
+//! Use it on a field with `#[serde(with = "palettevec::canonical")]`.
+//!
+//! Human-readable formats like JSON get the palette plus a list of
+//! `[palette index, run length]` pairs. Binary formats get the palette plus the
+//! indices packed into u64 words, using the same layout as
+//! [`PaletteVec::to_packed_longs`] with the smallest possible amount of bits.
+use std::hash::Hash;
+
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    index_buffer::IndexBuffer,
+    palette::{CountType, Palette, MAX_COUNT},
+    PaletteVec,
+};
+
+#[derive(Serialize, Deserialize)]
+struct Runs<T> {
+    palette: Vec<T>,
+    runs: Vec<(usize, usize)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Packed<T> {
+    len: usize,
+    bits: usize,
+    palette: Vec<T>,
+    words: Vec<u64>,
+}
+
+pub fn serialize<T, P, B, S>(vec: &PaletteVec<T, P, B>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Eq + Hash + Clone + Serialize,
+    P: Palette<T>,
+    B: IndexBuffer,
+    S: Serializer,
+{
+    if serializer.is_human_readable() {
+        let entries = vec.palette_entries_by_index();
+        let mut mapping = vec![0; vec.palette.index_bound()];
+        let mut palette = Vec::with_capacity(entries.len());
+        for (dense_index, (index, entry)) in entries.into_iter().enumerate() {
+            mapping[index] = dense_index;
+            palette.push(entry.value.clone());
+        }
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        PaletteVec::<T, P, B>::for_each_index(&vec.buffer, |index| {
+            let index = mapping[index];
+            match runs.last_mut() {
+                Some((last, length)) if *last == index => *length += 1,
+                _ => runs.push((index, 1)),
+            }
+        });
+        Runs { palette, runs }.serialize(serializer)
+    } else {
+        let packed = vec.to_packed_longs(0);
+        Packed {
+            len: vec.len(),
+            bits: packed.bits,
+            palette: packed.palette,
+            words: packed.longs,
+        }
+        .serialize(serializer)
+    }
+}
+
+// MAX_COUNT is usize::MAX with count-usize
+#[allow(clippy::absurd_extreme_comparisons)]
+pub fn deserialize<'de, T, P, B, D>(deserializer: D) -> Result<PaletteVec<T, P, B>, D::Error>
+where
+    T: Eq + Hash + Clone + Deserialize<'de>,
+    P: Palette<T>,
+    B: IndexBuffer,
+    D: Deserializer<'de>,
+{
+    if deserializer.is_human_readable() {
+        let Runs { palette, runs } = Runs::deserialize(deserializer)?;
+        let mut len: usize = 0;
+        for (index, length) in runs.iter() {
+            if *index >= palette.len() {
+                return Err(D::Error::custom(format!(
+                    "run index {} is out of bounds for a palette of {} values",
+                    index,
+                    palette.len()
+                )));
+            }
+            len = len
+                .checked_add(*length)
+                .filter(|len| *len <= MAX_COUNT)
+                .ok_or_else(|| D::Error::custom("too many elements"))?;
+        }
+        Ok(PaletteVec::from_dense_runs(&palette, &runs))
+    } else {
+        let Packed {
+            len,
+            bits,
+            palette,
+            words,
+        } = Packed::deserialize(deserializer)?;
+        if len > MAX_COUNT {
+            return Err(D::Error::custom("too many elements"));
+        }
+        PaletteVec::from_packed_longs(bits, len, &palette, &words).map_err(D::Error::custom)
+    }
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteVec<T, P, B> {
+    /// Like [`PaletteVec::from_dense_parts`], but with `(index, length)` runs
+    /// instead of single indices.
+    ///
+    /// Every index has to be smaller than `values.len()` and the lengths
+    /// must add up to at most `CountType::MAX`.
+    fn from_dense_runs(values: &[T], runs: &[(usize, usize)]) -> Self {
+        let mut counts: Vec<CountType> = vec![0; values.len()];
+        let mut len = 0;
+        for (index, length) in runs.iter() {
+            counts[*index] += *length as CountType;
+            len += *length;
+        }
+        let (mut result, mapping) = Self::from_dense_counts(values, counts);
+        if result.palette.index_size() == 0 {
+            result.buffer.zeroed(len);
+        } else {
+            result.extend_runs(runs.iter().map(|(index, length)| (mapping[*index], *length)));
+        }
+        result
+    }
+}
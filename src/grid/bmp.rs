@@ -0,0 +1,200 @@
+//! Uncompressed 8-bit palettized BMP files.
+//!
+//! The palette of the `PaletteVec` becomes the colour table of the file
+//! and the palette indices are written as pixels as they are. Only palettes
+//! with more than 256 index slots, but at most 256 used ones, get their
+//! indices packed densely first.
+use std::{
+    hash::Hash,
+    io::{self, Read, Write},
+};
+
+use crate::{index_buffer::IndexBuffer, palette::Palette, PaletteVec};
+
+use super::PaletteGrid2;
+
+const FILE_HEADER_SIZE: usize = 14;
+const INFO_HEADER_SIZE: usize = 40;
+/// BITMAPV5HEADER, the largest known info header
+const MAX_INFO_HEADER_SIZE: usize = 124;
+const MAX_COLORS: usize = 256;
+/// 72 DPI
+const PIXELS_PER_METER: u32 = 2835;
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Rows are padded to a multiple of 4 bytes.
+fn row_stride(width: usize) -> usize {
+    width.div_ceil(4) * 4
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteGrid2<T, P, B> {
+    /// Writes the grid as an uncompressed 8-bit BMP. `color` maps every palette value
+    /// to its `[r, g, b]` colour in the colour table.
+    ///
+    /// Fails with `InvalidInput` if the grid contains more than 256 unique values.
+    pub fn write_bmp<W: Write>(
+        &self,
+        mut writer: W,
+        mut color: impl FnMut(&T) -> [u8; 3],
+    ) -> io::Result<()> {
+        let palette = &self.data.palette;
+        let (table, remap) = if palette.index_bound() <= MAX_COLORS {
+            let table = (0..palette.index_bound())
+                .map(|index| palette.get_by_index(index))
+                .collect::<Vec<_>>();
+            (table, None)
+        } else if palette.len() <= MAX_COLORS {
+            let mut remap = vec![0u8; palette.index_bound()];
+            let mut table = Vec::with_capacity(palette.len());
+            for (index, entry) in self.data.palette_entries_by_index() {
+                remap[index] = table.len() as u8;
+                table.push(Some(entry));
+            }
+            (table, Some(remap))
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "BMP supports at most {} colours, but the grid has {}",
+                    MAX_COLORS,
+                    palette.len()
+                ),
+            ));
+        };
+        let colors = table.len().max(1);
+        let (width, height) = (self.width(), self.height());
+        let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "Grid too large for BMP");
+        let stride = row_stride(width);
+        let pixel_offset = FILE_HEADER_SIZE + INFO_HEADER_SIZE + colors * 4;
+        let image_size = stride.checked_mul(height).ok_or_else(too_large)?;
+        let file_size = u32::try_from(pixel_offset + image_size).map_err(|_| too_large())?;
+        let width = i32::try_from(width).map_err(|_| too_large())?;
+        let height = i32::try_from(height).map_err(|_| too_large())?;
+
+        let mut header = Vec::with_capacity(pixel_offset);
+        // File header
+        header.extend_from_slice(b"BM");
+        header.extend_from_slice(&file_size.to_le_bytes());
+        header.extend_from_slice(&[0; 4]);
+        header.extend_from_slice(&(pixel_offset as u32).to_le_bytes());
+        // Info header
+        header.extend_from_slice(&(INFO_HEADER_SIZE as u32).to_le_bytes());
+        header.extend_from_slice(&width.to_le_bytes());
+        header.extend_from_slice(&height.to_le_bytes());
+        header.extend_from_slice(&1u16.to_le_bytes());
+        header.extend_from_slice(&8u16.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        header.extend_from_slice(&(image_size as u32).to_le_bytes());
+        header.extend_from_slice(&PIXELS_PER_METER.to_le_bytes());
+        header.extend_from_slice(&PIXELS_PER_METER.to_le_bytes());
+        header.extend_from_slice(&(colors as u32).to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        // Colour table, unused palette slots stay black
+        for index in 0..colors {
+            let [r, g, b] = table
+                .get(index)
+                .copied()
+                .flatten()
+                .map_or([0; 3], |entry| color(&entry.value));
+            header.extend_from_slice(&[b, g, r, 0]);
+        }
+        writer.write_all(&header)?;
+
+        // Rows are stored bottom-up
+        let mut row = vec![0u8; stride];
+        for y in (0..self.height()).rev() {
+            let start = y * self.width();
+            for (pixel, index) in row
+                .iter_mut()
+                .zip(self.data.buffer.iter_range(start..start + self.width()))
+            {
+                *pixel = remap.as_ref().map_or(index as u8, |remap| remap[index]);
+            }
+            writer.write_all(&row)?;
+        }
+        Ok(())
+    }
+
+    /// Reads an uncompressed 8-bit BMP. `value` maps every colour of the colour table
+    /// to the value stored in the grid. Colours mapping to the same value are merged.
+    ///
+    /// Malformed files fail with `InvalidData`.
+    pub fn read_bmp<R: Read>(
+        mut reader: R,
+        mut value: impl FnMut([u8; 3]) -> T,
+    ) -> io::Result<Self> {
+        let mut file_header = [0u8; FILE_HEADER_SIZE];
+        reader.read_exact(&mut file_header)?;
+        if &file_header[..2] != b"BM" {
+            return Err(invalid_data("Not a BMP file"));
+        }
+        let pixel_offset = u32::from_le_bytes(file_header[10..14].try_into().unwrap()) as usize;
+
+        let mut info_size = [0u8; 4];
+        reader.read_exact(&mut info_size)?;
+        let info_size = u32::from_le_bytes(info_size) as usize;
+        if !(INFO_HEADER_SIZE..=MAX_INFO_HEADER_SIZE).contains(&info_size) {
+            return Err(invalid_data("Unsupported BMP info header"));
+        }
+        let mut info = vec![0u8; info_size - 4];
+        reader.read_exact(&mut info)?;
+        // Offsets are relative to the start of the info header, whose size was already read
+        let u16_at = |offset: usize| u16::from_le_bytes([info[offset - 4], info[offset - 3]]);
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(info[offset - 4..offset].try_into().unwrap());
+        let width = u32_at(4) as i32;
+        let height = u32_at(8) as i32;
+        if u16_at(12) != 1 || u16_at(14) != 8 {
+            return Err(invalid_data("Only 8-bit BMP files are supported"));
+        }
+        if u32_at(16) != 0 {
+            return Err(invalid_data("Only uncompressed BMP files are supported"));
+        }
+        if width < 0 || height == i32::MIN {
+            return Err(invalid_data("Invalid BMP dimensions"));
+        }
+        let colors = match u32_at(32) as usize {
+            0 => MAX_COLORS,
+            colors if colors <= MAX_COLORS => colors,
+            _ => return Err(invalid_data("Too many colours in the BMP colour table")),
+        };
+        let top_down = height < 0;
+        let (width, height) = (width as usize, height.unsigned_abs() as usize);
+
+        let mut table = vec![0u8; colors * 4];
+        reader.read_exact(&mut table)?;
+        let table_end = FILE_HEADER_SIZE + info_size + table.len();
+        if pixel_offset < table_end {
+            return Err(invalid_data("BMP pixel data overlaps the headers"));
+        }
+        io::copy(&mut (&mut reader).take((pixel_offset - table_end) as u64), &mut io::sink())?;
+        let values = table
+            .chunks_exact(4)
+            .map(|bgr| value([bgr[2], bgr[1], bgr[0]]))
+            .collect::<Vec<_>>();
+
+        let stride = row_stride(width);
+        let image_size = stride
+            .checked_mul(height)
+            .ok_or_else(|| invalid_data("Invalid BMP dimensions"))?;
+        let mut pixels = Vec::new();
+        (&mut reader).take(image_size as u64).read_to_end(&mut pixels)?;
+        if pixels.len() != image_size {
+            return Err(invalid_data("BMP pixel data is truncated"));
+        }
+
+        let mut data = PaletteVec::new();
+        for y in 0..height {
+            let stored_row = if top_down { y } else { height - 1 - y };
+            let row = &pixels[stored_row * stride..stored_row * stride + width];
+            if row.iter().any(|index| *index as usize >= colors) {
+                return Err(invalid_data("BMP pixel references a missing colour"));
+            }
+            data.extend(row.iter().map(|index| &values[*index as usize]));
+        }
+        Ok(Self::from_palette_vec(data, width, height))
+    }
+}
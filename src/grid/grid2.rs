@@ -0,0 +1,267 @@
+use std::{hash::Hash, iter::FusedIterator};
+
+use crate::{
+    index_buffer::IndexBuffer, palette::Palette, slice::PaletteSlice, PaletteVec, PaletteVecIter,
+};
+
+/// A fixed size 2D grid, for example an indexed colour image, stored in a `PaletteVec`.
+///
+/// Cells are stored row by row, so every row is a contiguous range.
+#[derive(Debug, Clone)]
+pub struct PaletteGrid2<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> {
+    pub(super) data: PaletteVec<T, P, B>,
+    width: usize,
+    height: usize,
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteGrid2<T, P, B> {
+    /// Creates a grid with every cell set to `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the amount of cells overflows `usize`.
+    pub fn new(width: usize, height: usize, value: T) -> Self {
+        Self {
+            data: PaletteVec::filled(value, Self::cell_count(width, height)),
+            width,
+            height,
+        }
+    }
+
+    /// Wraps an existing `PaletteVec` whose elements are stored row by row.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length doesn't match the dimensions or if the amount of cells
+    /// overflows `usize`.
+    pub fn from_palette_vec(data: PaletteVec<T, P, B>, width: usize, height: usize) -> Self {
+        assert_eq!(
+            data.len(),
+            Self::cell_count(width, height),
+            "PaletteVec length doesn't match the grid dimensions {}x{}",
+            width,
+            height
+        );
+        Self {
+            data,
+            width,
+            height,
+        }
+    }
+
+    fn cell_count(width: usize, height: usize) -> usize {
+        width.checked_mul(height).unwrap_or_else(|| {
+            panic!("Grid too large, dimensions {}x{} overflow usize", width, height)
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the amount of cells.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn offset_of(&self, x: usize, y: usize) -> Option<usize> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(x + y * self.width)
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        self.data.get(self.offset_of(x, y)?)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the coordinates are out of bounds.
+    pub fn set(&mut self, x: usize, y: usize, value: &T) {
+        let offset = self.offset_of(x, y).unwrap_or_else(|| {
+            panic!(
+                "Coordinates ({}, {}) out of bounds for PaletteGrid2 of size {}x{}",
+                x, y, self.width, self.height
+            )
+        });
+        self.data.set(offset, value);
+    }
+
+    /// Returns a borrowed view of the row `y`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y >= height`.
+    pub fn row(&self, y: usize) -> PaletteSlice<'_, T, P, B> {
+        self.assert_row(y);
+        self.data.slice(y * self.width..(y + 1) * self.width)
+    }
+
+    /// Returns an iterator over the cells of row `y`, from left to right.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y >= height`.
+    pub fn iter_row(&self, y: usize) -> PaletteVecIter<'_, T, P, B> {
+        self.assert_row(y);
+        self.data.iter_range(y * self.width..(y + 1) * self.width)
+    }
+
+    /// Returns an iterator over the cells of column `x`, from top to bottom.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x >= width`.
+    pub fn iter_column(&self, x: usize) -> PaletteGrid2Column<'_, T, P, B> {
+        assert!(
+            x < self.width,
+            "Column {} out of bounds for PaletteGrid2 of width {}",
+            x,
+            self.width
+        );
+        PaletteGrid2Column {
+            grid: self,
+            x,
+            y: 0,
+            end: self.height,
+        }
+    }
+
+    /// Returns an iterator over all rows, from top to bottom.
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = PaletteSlice<'_, T, P, B>> + '_ {
+        (0..self.height).map(|y| self.row(y))
+    }
+
+    fn assert_row(&self, y: usize) {
+        assert!(
+            y < self.height,
+            "Row {} out of bounds for PaletteGrid2 of height {}",
+            y,
+            self.height
+        );
+    }
+
+    /// Copies all cells of `source` into this grid, with the top left corner of
+    /// `source` placed at `(x, y)`. Cells that would land outside of this grid are skipped.
+    pub fn blit<P2, B2>(&mut self, source: &PaletteGrid2<T, P2, B2>, x: usize, y: usize)
+    where
+        P2: Palette<T>,
+        B2: IndexBuffer,
+    {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let width = source.width.min(self.width - x);
+        let height = source.height.min(self.height - y);
+        if width == 0 {
+            return;
+        }
+        let mut row = Vec::with_capacity(width);
+        for source_y in 0..height {
+            row.clear();
+            row.extend(source.iter_row(source_y).take(width).cloned());
+            self.data.set_range((y + source_y) * self.width + x, &row);
+        }
+    }
+
+    /// Returns a new grid containing the `width` x `height` cells starting at `(x, y)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rectangle is out of bounds.
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Self {
+        assert!(
+            x.checked_add(width).is_some_and(|end| end <= self.width)
+                && y.checked_add(height).is_some_and(|end| end <= self.height),
+            "Crop of {}x{} at ({}, {}) out of bounds for PaletteGrid2 of size {}x{}",
+            width,
+            height,
+            x,
+            y,
+            self.width,
+            self.height
+        );
+        let mut data = PaletteVec::new();
+        for row in y..y + height {
+            let start = row * self.width + x;
+            data.extend(self.data.iter_range(start..start + width));
+        }
+        Self {
+            data,
+            width,
+            height,
+        }
+    }
+
+    /// See [`PaletteVec::optimize`].
+    pub fn optimize(&mut self) {
+        self.data.optimize();
+    }
+
+    pub fn as_palette_vec(&self) -> &PaletteVec<T, P, B> {
+        &self.data
+    }
+
+    pub fn into_palette_vec(self) -> PaletteVec<T, P, B> {
+        self.data
+    }
+}
+
+// COLUMN ITERATOR
+#[derive(Debug, Clone)]
+pub struct PaletteGrid2Column<'a, T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> {
+    grid: &'a PaletteGrid2<T, P, B>,
+    x: usize,
+    y: usize,
+    end: usize,
+}
+
+impl<'a, T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> Iterator
+    for PaletteGrid2Column<'a, T, P, B>
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.y >= self.end {
+            return None;
+        }
+        self.y += 1;
+        self.grid.get(self.x, self.y - 1)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.y;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> DoubleEndedIterator
+    for PaletteGrid2Column<'_, T, P, B>
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.y >= self.end {
+            return None;
+        }
+        self.end -= 1;
+        self.grid.get(self.x, self.end)
+    }
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> ExactSizeIterator
+    for PaletteGrid2Column<'_, T, P, B>
+{
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> FusedIterator
+    for PaletteGrid2Column<'_, T, P, B>
+{
+}
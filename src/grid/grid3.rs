@@ -0,0 +1,386 @@
+use std::{fmt, hash::Hash, iter::FusedIterator};
+
+use crate::{index_buffer::IndexBuffer, palette::Palette, PaletteVec, PaletteVecIter};
+
+use super::{morton_decode, morton_encode, Axis, GridLayout};
+
+/// A fixed size 3D grid, for example a voxel chunk, stored in a `PaletteVec`.
+#[derive(Debug, Clone)]
+pub struct PaletteGrid3<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> {
+    data: PaletteVec<T, P, B>,
+    dimensions: [usize; 3],
+    layout: GridLayout,
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteGrid3<T, P, B> {
+    /// Creates a grid with every cell set to `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the amount of cells overflows `usize` or if the layout is
+    /// `Morton` and a dimension is not a power of two.
+    pub fn new(width: usize, height: usize, depth: usize, layout: GridLayout, value: T) -> Self {
+        let dimensions = [width, height, depth];
+        Self::check_layout(dimensions, layout);
+        Self {
+            data: PaletteVec::filled(value, Self::cell_count(dimensions)),
+            dimensions,
+            layout,
+        }
+    }
+
+    /// Wraps an existing `PaletteVec` whose elements are already in the given layout.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length doesn't match the dimensions, if the amount of cells
+    /// overflows `usize` or if the layout is `Morton` and a dimension is not a power of two.
+    pub fn from_palette_vec(
+        data: PaletteVec<T, P, B>,
+        width: usize,
+        height: usize,
+        depth: usize,
+        layout: GridLayout,
+    ) -> Self {
+        let dimensions = [width, height, depth];
+        Self::check_layout(dimensions, layout);
+        assert_eq!(
+            data.len(),
+            Self::cell_count(dimensions),
+            "PaletteVec length doesn't match the grid dimensions {}x{}x{}",
+            width,
+            height,
+            depth
+        );
+        Self {
+            data,
+            dimensions,
+            layout,
+        }
+    }
+
+    fn cell_count(dimensions: [usize; 3]) -> usize {
+        dimensions
+            .iter()
+            .try_fold(1usize, |cells, dimension| cells.checked_mul(*dimension))
+            .unwrap_or_else(|| panic!("Grid too large, dimensions {:?} overflow usize", dimensions))
+    }
+
+    fn check_layout(dimensions: [usize; 3], layout: GridLayout) {
+        if layout == GridLayout::Morton {
+            assert!(
+                dimensions.iter().all(|dimension| dimension.is_power_of_two()),
+                "Morton layout requires power of two dimensions, got {:?}",
+                dimensions
+            );
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.dimensions[0]
+    }
+
+    pub fn height(&self) -> usize {
+        self.dimensions[1]
+    }
+
+    pub fn depth(&self) -> usize {
+        self.dimensions[2]
+    }
+
+    pub fn dimensions(&self) -> [usize; 3] {
+        self.dimensions
+    }
+
+    pub fn layout(&self) -> GridLayout {
+        self.layout
+    }
+
+    /// Returns the amount of cells.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the offset of the cell within the underlying `PaletteVec`,
+    /// or `None` if the coordinates are out of bounds.
+    pub fn offset_of(&self, x: usize, y: usize, z: usize) -> Option<usize> {
+        let [width, height, depth] = self.dimensions;
+        if x >= width || y >= height || z >= depth {
+            return None;
+        }
+        Some(match self.layout {
+            GridLayout::Linear => x + y * width + z * width * height,
+            GridLayout::Morton => morton_encode([x, y, z], self.morton_bits()),
+        })
+    }
+
+    /// Returns the coordinates of the cell at `offset` within the underlying `PaletteVec`.
+    pub fn coords_of(&self, offset: usize) -> [usize; 3] {
+        debug_assert!(offset < self.len());
+        let [width, height, _] = self.dimensions;
+        match self.layout {
+            GridLayout::Linear => [
+                offset % width,
+                offset / width % height,
+                offset / (width * height),
+            ],
+            GridLayout::Morton => morton_decode(offset, self.morton_bits()),
+        }
+    }
+
+    fn morton_bits(&self) -> [u32; 3] {
+        self.dimensions.map(|dimension| dimension.trailing_zeros())
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Option<&T> {
+        self.data.get(self.offset_of(x, y, z)?)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the coordinates are out of bounds.
+    pub fn set(&mut self, x: usize, y: usize, z: usize, value: &T) {
+        let offset = self.offset_of(x, y, z).unwrap_or_else(|| {
+            panic!(
+                "Coordinates ({}, {}, {}) out of bounds for PaletteGrid3 of size {:?}",
+                x, y, z, self.dimensions
+            )
+        });
+        self.data.set(offset, value);
+    }
+
+    /// Sets every cell within `min..max` on all axes to `value`.
+    /// `min` is inclusive, `max` is exclusive.
+    ///
+    /// With the linear layout, whole rows are filled at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the box is out of bounds.
+    pub fn fill_box(&mut self, min: [usize; 3], max: [usize; 3], value: &T) {
+        for axis in 0..3 {
+            assert!(
+                min[axis] <= max[axis] && max[axis] <= self.dimensions[axis],
+                "Box {:?}..{:?} out of bounds for PaletteGrid3 of size {:?}",
+                min,
+                max,
+                self.dimensions
+            );
+        }
+        if min.iter().zip(max.iter()).any(|(min, max)| min == max) {
+            return;
+        }
+        for z in min[2]..max[2] {
+            for y in min[1]..max[1] {
+                match self.layout {
+                    GridLayout::Linear => {
+                        let start = self.offset_of(min[0], y, z).unwrap();
+                        self.data.fill_range(start..start + max[0] - min[0], value);
+                    }
+                    GridLayout::Morton => {
+                        for x in min[0]..max[0] {
+                            let offset = self.offset_of(x, y, z).unwrap();
+                            self.data.set(offset, value);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns an iterator over the 2D layer where the coordinate on `axis` is `index`.
+    ///
+    /// The remaining two coordinates are iterated in x, y, z order, with the first one
+    /// changing fastest. For example, `Axis::Y` iterates over x first, then z.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds for the axis.
+    pub fn iter_layer(&self, axis: Axis, index: usize) -> PaletteGrid3Layer<'_, T, P, B> {
+        let axis_index = axis as usize;
+        assert!(
+            index < self.dimensions[axis_index],
+            "Layer {} out of bounds for {:?} axis of size {}",
+            index,
+            axis,
+            self.dimensions[axis_index]
+        );
+        let plane = match axis {
+            Axis::X => [1, 2],
+            Axis::Y => [0, 2],
+            Axis::Z => [0, 1],
+        };
+        PaletteGrid3Layer {
+            grid: self,
+            axis: axis_index,
+            index,
+            plane,
+            offset: 0,
+            end: self.dimensions[plane[0]] * self.dimensions[plane[1]],
+        }
+    }
+
+    /// Returns an iterator over all cells and their coordinates, in storage order.
+    pub fn iter_with_coords(&self) -> PaletteGrid3Iter<'_, T, P, B> {
+        PaletteGrid3Iter {
+            grid: self,
+            values: self.data.iter(),
+            offset: 0,
+        }
+    }
+
+    /// Returns the value of every cell if all cells hold the same value.
+    ///
+    /// This only looks at the palette, so it is O(1).
+    pub fn uniform_value(&self) -> Option<&T> {
+        if self.data.unique_values() != 1 {
+            return None;
+        }
+        self.data.iter_palette_entries().next().map(|entry| &entry.value)
+    }
+
+    /// See [`PaletteVec::optimize`].
+    pub fn optimize(&mut self) {
+        self.data.optimize();
+    }
+
+    pub fn as_palette_vec(&self) -> &PaletteVec<T, P, B> {
+        &self.data
+    }
+
+    pub fn into_palette_vec(self) -> PaletteVec<T, P, B> {
+        self.data
+    }
+}
+
+// LAYER ITERATOR
+#[derive(Debug, Clone)]
+pub struct PaletteGrid3Layer<'a, T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> {
+    grid: &'a PaletteGrid3<T, P, B>,
+    axis: usize,
+    index: usize,
+    /// The two axes spanning the layer
+    plane: [usize; 2],
+    offset: usize,
+    end: usize,
+}
+
+impl<'a, T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteGrid3Layer<'a, T, P, B> {
+    fn value_at(&self, offset: usize) -> &'a T {
+        let first = self.grid.dimensions[self.plane[0]];
+        let mut coords = [0; 3];
+        coords[self.axis] = self.index;
+        coords[self.plane[0]] = offset % first;
+        coords[self.plane[1]] = offset / first;
+        let [x, y, z] = coords;
+        self.grid.get(x, y, z).unwrap()
+    }
+}
+
+impl<'a, T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> Iterator
+    for PaletteGrid3Layer<'a, T, P, B>
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.end {
+            return None;
+        }
+        self.offset += 1;
+        Some(self.value_at(self.offset - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.offset;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> DoubleEndedIterator
+    for PaletteGrid3Layer<'_, T, P, B>
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.end {
+            return None;
+        }
+        self.end -= 1;
+        Some(self.value_at(self.end))
+    }
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> ExactSizeIterator
+    for PaletteGrid3Layer<'_, T, P, B>
+{
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> FusedIterator
+    for PaletteGrid3Layer<'_, T, P, B>
+{
+}
+
+// COORDINATE ITERATOR
+pub struct PaletteGrid3Iter<'a, T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> {
+    grid: &'a PaletteGrid3<T, P, B>,
+    values: PaletteVecIter<'a, T, P, B>,
+    offset: usize,
+}
+
+// Derives can't see that the value iterator needs `B::Iter` bounds
+impl<'a, T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> Clone
+    for PaletteGrid3Iter<'a, T, P, B>
+where
+    PaletteVecIter<'a, T, P, B>: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            grid: self.grid,
+            values: self.values.clone(),
+            offset: self.offset,
+        }
+    }
+}
+
+impl<'a, T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> fmt::Debug
+    for PaletteGrid3Iter<'a, T, P, B>
+where
+    PaletteGrid3<T, P, B>: fmt::Debug,
+    PaletteVecIter<'a, T, P, B>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PaletteGrid3Iter")
+            .field("grid", &self.grid)
+            .field("values", &self.values)
+            .field("offset", &self.offset)
+            .finish()
+    }
+}
+
+impl<'a, T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> Iterator
+    for PaletteGrid3Iter<'a, T, P, B>
+{
+    type Item = ([usize; 3], &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.values.next()?;
+        self.offset += 1;
+        Some((self.grid.coords_of(self.offset - 1), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
+    }
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> ExactSizeIterator
+    for PaletteGrid3Iter<'_, T, P, B>
+{
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> FusedIterator
+    for PaletteGrid3Iter<'_, T, P, B>
+{
+}
@@ -0,0 +1,65 @@
+//! Grids are fixed size, multi dimensional views on top of a `PaletteVec`.
+//!
+//! They take care of translating coordinates into offsets, so voxel chunks
+//! and similar data don't need hand written `x + y * W + z * W * H` indexing.
+
+mod bmp;
+pub mod grid2;
+pub mod grid3;
+
+pub use self::grid2::PaletteGrid2;
+pub use self::grid3::PaletteGrid3;
+
+/// How coordinates are mapped to offsets within the underlying `PaletteVec`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GridLayout {
+    /// Row-major: x changes fastest, then y, then z.
+    #[default]
+    Linear,
+    /// Z-order curve: the bits of all coordinates are interleaved, which keeps
+    /// nearby cells close together and tends to produce longer runs.
+    ///
+    /// All dimensions must be powers of two. If they differ, the remaining bits
+    /// of the larger dimensions are appended once the smaller ones run out.
+    Morton,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+/// Interleaves the bits of all coordinates, lowest bits first.
+/// `bits[i]` is the amount of bits of the i-th dimension.
+pub(crate) fn morton_encode<const N: usize>(coords: [usize; N], bits: [u32; N]) -> usize {
+    let max_bits = bits.iter().copied().max().unwrap_or(0);
+    let mut offset = 0;
+    let mut out_bit = 0;
+    for bit in 0..max_bits {
+        for axis in 0..N {
+            if bit < bits[axis] {
+                offset |= ((coords[axis] >> bit) & 1) << out_bit;
+                out_bit += 1;
+            }
+        }
+    }
+    offset
+}
+
+/// Inverse of [`morton_encode`].
+pub(crate) fn morton_decode<const N: usize>(offset: usize, bits: [u32; N]) -> [usize; N] {
+    let max_bits = bits.iter().copied().max().unwrap_or(0);
+    let mut coords = [0; N];
+    let mut in_bit = 0;
+    for bit in 0..max_bits {
+        for axis in 0..N {
+            if bit < bits[axis] {
+                coords[axis] |= ((offset >> in_bit) & 1) << bit;
+                in_bit += 1;
+            }
+        }
+    }
+    coords
+}
@@ -4,11 +4,13 @@
 //! It does NOT store u64-boundary crossing indices. This means slightly more
 //! memory usage for slightly faster access times. This is a good default.
 
+use std::{iter::FusedIterator, ops::Range};
+
 use rustc_hash::FxHashMap;
 
-use crate::MemoryUsage;
+use crate::{InvariantError, MemoryUsage};
 use crate::palette::CountType;
-use super::IndexBuffer;
+use super::{check_packed_layout, packed, IndexBuffer};
 
 /// An `IndexBuffer` implementation that stores indices
 /// packed tightly into a `Vec<u64>`.
@@ -16,7 +18,7 @@ use super::IndexBuffer;
 /// It does NOT store u64-boundary crossing indices. This means slightly more
 /// memory usage for slightly faster access times. This is a good default.
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(feature = "bitcode", derive(bitcode::Encode, bitcode::Decode))]
 pub struct AlignedIndexBuffer {
     index_size: usize,
@@ -26,6 +28,31 @@ pub struct AlignedIndexBuffer {
     storage: Vec<u64>,
 }
 
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for AlignedIndexBuffer {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "AlignedIndexBuffer")]
+        struct Unchecked {
+            index_size: usize,
+            indices_per_u64: u8,
+            mask: u64,
+            len: usize,
+            storage: Vec<u64>,
+        }
+        let unchecked = Unchecked::deserialize(deserializer)?;
+        let buffer = Self {
+            index_size: unchecked.index_size,
+            indices_per_u64: unchecked.indices_per_u64,
+            mask: unchecked.mask,
+            len: unchecked.len,
+            storage: unchecked.storage,
+        };
+        buffer.check_invariants().map_err(serde::de::Error::custom)?;
+        Ok(buffer)
+    }
+}
+
 impl AlignedIndexBuffer {
     fn set_index_with_index_size(
         &mut self,
@@ -150,6 +177,26 @@ impl IndexBuffer for AlignedIndexBuffer {
         (None, None)
     }
 
+    fn truncate(&mut self, new_len: usize) -> FxHashMap<usize, CountType> {
+        debug_assert!(new_len <= self.len);
+        let mut removed_indices = FxHashMap::default();
+        if new_len == self.len {
+            return removed_indices;
+        }
+        if self.index_size == 0 {
+            removed_indices.insert(0, (self.len - new_len) as CountType);
+            self.len = new_len;
+            return removed_indices;
+        }
+        for i in new_len..self.len {
+            *removed_indices.entry(self._get_index(i)).or_insert(0) += 1;
+        }
+        self.storage
+            .truncate(new_len.div_ceil(self.indices_per_u64 as usize));
+        self.len = new_len;
+        removed_indices
+    }
+
     fn len(&self) -> usize {
         self.len
     }
@@ -243,7 +290,7 @@ impl IndexBuffer for AlignedIndexBuffer {
         let indices_per_u64 = self.indices_per_u64 as usize;
 
         // Check if we need a new storage u64
-        if self.len % indices_per_u64 == 0 {
+        if self.len.is_multiple_of(indices_per_u64) {
             self.storage.push(index as u64);
             self.len += 1;
             return;
@@ -268,13 +315,85 @@ impl IndexBuffer for AlignedIndexBuffer {
         self.len -= 1;
 
         // Check if it's the last index in the storage u64
-        if self.len % indices_per_u64 == 0 {
+        if self.len.is_multiple_of(indices_per_u64) {
             self.storage.pop();
         }
 
         Some(index)
     }
 
+    fn extend_indices(&mut self, indices: &[usize]) {
+        if self.index_size > 0 {
+            packed::extend(&mut self.storage, self.index_size, self.len, indices);
+        }
+        self.len += indices.len();
+    }
+
+    fn extend_packed(&mut self, words: &[u64], bits: usize, len: usize) {
+        if self.index_size > 0 {
+            packed::extend_packed(&mut self.storage, self.index_size, self.len, words, bits, len);
+        }
+        self.len += len;
+    }
+
+    fn insert_index(&mut self, offset: usize, index: usize) {
+        debug_assert!(offset <= self.len);
+        if self.index_size == 0 {
+            self.len += 1;
+            return;
+        }
+        packed::insert(&mut self.storage, self.index_size, self.len, offset, index);
+        self.len += 1;
+    }
+
+    fn remove_index(&mut self, offset: usize) -> usize {
+        debug_assert!(offset < self.len);
+        if self.index_size == 0 {
+            self.len -= 1;
+            return 0;
+        }
+        let index = packed::remove(&mut self.storage, self.index_size, self.len, offset);
+        self.len -= 1;
+        index
+    }
+
+    fn swap_indices(&mut self, a: usize, b: usize) {
+        debug_assert!(a < self.len && b < self.len);
+        if self.index_size == 0 {
+            return;
+        }
+        packed::swap(&mut self.storage, self.index_size, a, b);
+    }
+
+    fn reverse_indices(&mut self) {
+        if self.index_size == 0 {
+            return;
+        }
+        packed::reverse(&mut self.storage, self.index_size, self.len);
+    }
+
+    fn bits_per_index(&self) -> usize {
+        self.index_size
+    }
+
+    fn packed_words(&self) -> &[u64] {
+        &self.storage
+    }
+
+    fn check_invariants(&self) -> Result<(), InvariantError> {
+        // The mask of 64 bit indices would overflow
+        if self.index_size > 63 {
+            return Err(InvariantError::UnsupportedIndexSize(self.index_size));
+        }
+        check_packed_layout(
+            self.index_size,
+            self.indices_per_u64,
+            self.mask,
+            self.len,
+            &self.storage,
+        )
+    }
+
     fn set_index(&mut self, offset: usize, index: usize) -> usize {
         debug_assert!(
             self.index_size > 0,
@@ -289,15 +408,40 @@ impl IndexBuffer for AlignedIndexBuffer {
         self._get_index(offset)
     }
 
+    fn fill_range(&mut self, range: Range<usize>, index: usize) -> FxHashMap<usize, CountType> {
+        debug_assert!(range.end <= self.len);
+        if self.index_size == 0 {
+            let mut overwritten = FxHashMap::default();
+            if !range.is_empty() {
+                overwritten.insert(0, range.len() as CountType);
+            }
+            return overwritten;
+        }
+        packed::fill(&mut self.storage, self.index_size, range, index)
+    }
+
+    fn replace_index(&mut self, from: usize, to: usize) {
+        if self.index_size == 0 || from == to {
+            return;
+        }
+        packed::replace(&mut self.storage, self.index_size, self.len, from, to);
+    }
+
     type Iter<'a>
         = AlignedIndexIterator<'a>
     where
         Self: 'a;
 
     fn iter(&self) -> Self::Iter<'_> {
+        self.iter_range(0..self.len)
+    }
+
+    fn iter_range(&self, range: Range<usize>) -> Self::Iter<'_> {
+        debug_assert!(range.start <= range.end && range.end <= self.len);
         AlignedIndexIterator {
             buffer: self,
-            offset: 0,
+            offset: range.start,
+            end: range.end,
         }
     }
 }
@@ -307,18 +451,49 @@ impl IndexBuffer for AlignedIndexBuffer {
 pub struct AlignedIndexIterator<'a> {
     buffer: &'a AlignedIndexBuffer,
     offset: usize,
+    end: usize,
 }
 
 impl<'a> Iterator for AlignedIndexIterator<'a> {
     type Item = usize;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.offset >= self.buffer.len {
+        if self.offset >= self.end {
             None
         } else {
-            let index = self.buffer.get_index(self.offset);
+            let index = self.buffer._get_index(self.offset);
             self.offset += 1;
             Some(index)
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.offset;
+        (remaining, Some(remaining))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.offset = self.offset.saturating_add(n).min(self.end);
+        self.next()
+    }
+}
+
+impl<'a> DoubleEndedIterator for AlignedIndexIterator<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.end {
+            None
+        } else {
+            self.end -= 1;
+            Some(self.buffer._get_index(self.end))
+        }
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.end = self.end.saturating_sub(n).max(self.offset);
+        self.next_back()
+    }
 }
+
+impl<'a> ExactSizeIterator for AlignedIndexIterator<'a> {}
+
+impl<'a> FusedIterator for AlignedIndexIterator<'a> {}
@@ -1,8 +1,10 @@
+use std::{iter::FusedIterator, ops::Range};
+
 use rustc_hash::FxHashMap;
 
-use crate::MemoryUsage;
+use crate::{InvariantError, MemoryUsage};
 use crate::palette::CountType;
-use super::IndexBuffer;
+use super::{check_packed_layout, packed, IndexBuffer};
 
 fn map_index_size(from_palette: usize) -> usize {
     debug_assert!(from_palette <= 64);
@@ -19,7 +21,7 @@ fn map_index_size(from_palette: usize) -> usize {
 /// It accomplishes this by rounding up the index_size to the nearest value that
 /// is a power of 2 and by skipping 2 and 4.
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(feature = "bitcode", derive(bitcode::Encode, bitcode::Decode))]
 pub struct FastIndexBuffer {
     index_size: usize,
@@ -30,6 +32,33 @@ pub struct FastIndexBuffer {
     storage: Vec<u64>,
 }
 
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for FastIndexBuffer {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "FastIndexBuffer")]
+        struct Unchecked {
+            index_size: usize,
+            index_size_log_2: usize,
+            indices_per_u64: u8,
+            mask: u64,
+            len: usize,
+            storage: Vec<u64>,
+        }
+        let unchecked = Unchecked::deserialize(deserializer)?;
+        let buffer = Self {
+            index_size: unchecked.index_size,
+            index_size_log_2: unchecked.index_size_log_2,
+            indices_per_u64: unchecked.indices_per_u64,
+            mask: unchecked.mask,
+            len: unchecked.len,
+            storage: unchecked.storage,
+        };
+        buffer.check_invariants().map_err(serde::de::Error::custom)?;
+        Ok(buffer)
+    }
+}
+
 impl FastIndexBuffer {
     fn set_index_with_index_size(
         &mut self,
@@ -142,8 +171,8 @@ impl FastIndexBuffer {
             let byte_ptr = self.storage.as_ptr() as *const u8;
             let base_offset = storage_index << self.index_size_log_2;
             unsafe {
-                for i in 0..count {
-                    buf[i] = *byte_ptr.add(base_offset + i) as usize;
+                for (i, slot) in buf.iter_mut().enumerate().take(count) {
+                    *slot = *byte_ptr.add(base_offset + i) as usize;
                 }
             }
             return self.indices_per_u64 as usize;
@@ -160,9 +189,9 @@ impl FastIndexBuffer {
             }
         };
 
-        for i in 0..count {
+        for (i, slot) in buf.iter_mut().enumerate().take(count) {
             let bit_offset = i << self.index_size_log_2;
-            buf[i] = ((target_u64 >> bit_offset) & self.mask) as usize;
+            *slot = ((target_u64 >> bit_offset) & self.mask) as usize;
         }
         count
     }
@@ -227,6 +256,26 @@ impl IndexBuffer for FastIndexBuffer {
         (None, None)
     }
 
+    fn truncate(&mut self, new_len: usize) -> FxHashMap<usize, CountType> {
+        debug_assert!(new_len <= self.len);
+        let mut removed_indices = FxHashMap::default();
+        if new_len == self.len {
+            return removed_indices;
+        }
+        if self.index_size == 0 {
+            removed_indices.insert(0, (self.len - new_len) as CountType);
+            self.len = new_len;
+            return removed_indices;
+        }
+        for i in new_len..self.len {
+            *removed_indices.entry(self._get_index(i)).or_insert(0) += 1;
+        }
+        self.storage
+            .truncate(new_len.div_ceil(self.indices_per_u64 as usize));
+        self.len = new_len;
+        removed_indices
+    }
+
     fn len(&self) -> usize {
         self.len
     }
@@ -341,7 +390,7 @@ impl IndexBuffer for FastIndexBuffer {
         let indices_per_u64 = self.indices_per_u64 as usize;
 
         // Check if we need a new storage u64
-        if self.len % indices_per_u64 == 0 {
+        if self.len.is_multiple_of(indices_per_u64) {
             self.storage.push(index as u64);
             self.len += 1;
             return;
@@ -366,13 +415,89 @@ impl IndexBuffer for FastIndexBuffer {
         self.len -= 1;
 
         // Check if it's the last index in the storage u64
-        if self.len % indices_per_u64 == 0 {
+        if self.len.is_multiple_of(indices_per_u64) {
             self.storage.pop();
         }
 
         Some(index)
     }
 
+    fn extend_indices(&mut self, indices: &[usize]) {
+        if self.index_size > 0 {
+            packed::extend(&mut self.storage, self.index_size, self.len, indices);
+        }
+        self.len += indices.len();
+    }
+
+    fn extend_packed(&mut self, words: &[u64], bits: usize, len: usize) {
+        if self.index_size > 0 {
+            packed::extend_packed(&mut self.storage, self.index_size, self.len, words, bits, len);
+        }
+        self.len += len;
+    }
+
+    fn insert_index(&mut self, offset: usize, index: usize) {
+        debug_assert!(offset <= self.len);
+        if self.index_size == 0 {
+            self.len += 1;
+            return;
+        }
+        packed::insert(&mut self.storage, self.index_size, self.len, offset, index);
+        self.len += 1;
+    }
+
+    fn remove_index(&mut self, offset: usize) -> usize {
+        debug_assert!(offset < self.len);
+        if self.index_size == 0 {
+            self.len -= 1;
+            return 0;
+        }
+        let index = packed::remove(&mut self.storage, self.index_size, self.len, offset);
+        self.len -= 1;
+        index
+    }
+
+    fn swap_indices(&mut self, a: usize, b: usize) {
+        debug_assert!(a < self.len && b < self.len);
+        if self.index_size == 0 {
+            return;
+        }
+        packed::swap(&mut self.storage, self.index_size, a, b);
+    }
+
+    fn reverse_indices(&mut self) {
+        if self.index_size == 0 {
+            return;
+        }
+        packed::reverse(&mut self.storage, self.index_size, self.len);
+    }
+
+    fn bits_per_index(&self) -> usize {
+        self.index_size
+    }
+
+    fn packed_words(&self) -> &[u64] {
+        &self.storage
+    }
+
+    fn check_invariants(&self) -> Result<(), InvariantError> {
+        if !matches!(self.index_size, 0 | 8 | 16 | 32 | 64) {
+            return Err(InvariantError::UnsupportedIndexSize(self.index_size));
+        }
+        if self.index_size != 0
+            && self.index_size_log_2 != self.index_size.trailing_zeros() as usize
+        {
+            return Err(InvariantError::InconsistentField("index_size_log_2"));
+        }
+        check_packed_layout(
+            self.index_size,
+            self.indices_per_u64,
+            self.mask,
+            self.len,
+            &self.storage,
+        )
+    }
+
     fn set_index(&mut self, offset: usize, index: usize) -> usize {
         debug_assert!(
             self.index_size > 0,
@@ -387,15 +512,40 @@ impl IndexBuffer for FastIndexBuffer {
         self._get_index(offset)
     }
 
+    fn fill_range(&mut self, range: Range<usize>, index: usize) -> FxHashMap<usize, CountType> {
+        debug_assert!(range.end <= self.len);
+        if self.index_size == 0 {
+            let mut overwritten = FxHashMap::default();
+            if !range.is_empty() {
+                overwritten.insert(0, range.len() as CountType);
+            }
+            return overwritten;
+        }
+        packed::fill(&mut self.storage, self.index_size, range, index)
+    }
+
+    fn replace_index(&mut self, from: usize, to: usize) {
+        if self.index_size == 0 || from == to {
+            return;
+        }
+        packed::replace(&mut self.storage, self.index_size, self.len, from, to);
+    }
+
     type Iter<'a>
         = FastIndexIterator<'a>
     where
         Self: 'a;
 
     fn iter(&self) -> Self::Iter<'_> {
+        self.iter_range(0..self.len)
+    }
+
+    fn iter_range(&self, range: Range<usize>) -> Self::Iter<'_> {
+        debug_assert!(range.start <= range.end && range.end <= self.len);
         FastIndexIterator {
             buffer: self,
-            offset: 0,
+            offset: range.start,
+            end: range.end,
             bulk_buf: [0; 8],
             bulk_pos: 0,
             bulk_count: 0,
@@ -405,13 +555,18 @@ impl IndexBuffer for FastIndexBuffer {
 }
 
 // ITERATOR
+/// Iterates front to back by decoding a whole u64 at once into `bulk_buf`.
+/// Iterating back to front reads single indices.
 #[derive(Debug, Clone)]
 pub struct FastIndexIterator<'a> {
     buffer: &'a FastIndexBuffer,
     offset: usize,
+    end: usize,
     bulk_buf: [usize; 8],
+    /// Slot of `offset` inside the decoded u64
     bulk_pos: usize,
     bulk_count: usize,
+    /// The u64 that is currently decoded into `bulk_buf`
     storage_index: usize,
 }
 
@@ -419,7 +574,7 @@ impl<'a> Iterator for FastIndexIterator<'a> {
     type Item = usize;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.offset >= self.buffer.len {
+        if self.offset >= self.end {
             return None;
         }
 
@@ -430,18 +585,11 @@ impl<'a> Iterator for FastIndexIterator<'a> {
 
         if self.bulk_pos == self.bulk_count {
             let per_u64 = self.buffer.indices_per_u64 as usize;
-            let remaining = self.buffer.len - self.offset;
-
+            self.storage_index = self.offset / per_u64;
             self.bulk_count = self
                 .buffer
                 .get_index_bulk(self.storage_index, &mut self.bulk_buf);
-
-            if remaining < per_u64 {
-                self.bulk_count = remaining;
-            }
-
-            self.storage_index += 1;
-            self.bulk_pos = 0;
+            self.bulk_pos = self.offset % per_u64;
         }
 
         let value = self.bulk_buf[self.bulk_pos];
@@ -449,4 +597,43 @@ impl<'a> Iterator for FastIndexIterator<'a> {
         self.offset += 1;
         Some(value)
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.offset;
+        (remaining, Some(remaining))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.offset = self.offset.saturating_add(n).min(self.end);
+        if self.buffer.index_size > 0 {
+            let per_u64 = self.buffer.indices_per_u64 as usize;
+            if self.bulk_count > 0 && self.offset / per_u64 == self.storage_index {
+                // Still inside the decoded u64
+                self.bulk_pos = self.offset % per_u64;
+            } else {
+                self.bulk_pos = 0;
+                self.bulk_count = 0;
+            }
+        }
+        self.next()
+    }
+}
+
+impl<'a> DoubleEndedIterator for FastIndexIterator<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.end {
+            return None;
+        }
+        self.end -= 1;
+        Some(self.buffer._get_index(self.end))
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.end = self.end.saturating_sub(n).max(self.offset);
+        self.next_back()
+    }
 }
+
+impl<'a> ExactSizeIterator for FastIndexIterator<'a> {}
+
+impl<'a> FusedIterator for FastIndexIterator<'a> {}
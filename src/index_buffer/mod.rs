@@ -2,17 +2,57 @@
 //!
 //! AlignedIndexBuffer is a good default.
 
+use std::{iter::FusedIterator, ops::Range};
+
 use rustc_hash::FxHashMap;
 
-use crate::MemoryUsage;
+use crate::{InvariantError, MemoryUsage};
 use crate::palette::CountType;
 
 pub mod aligned;
 pub mod fast;
+pub(crate) mod packed;
 
 pub use self::aligned::AlignedIndexBuffer;
 pub use self::fast::FastIndexBuffer;
 
+/// Checks the fields shared by the packed index buffers. The index size
+/// has to be checked for support by the caller.
+fn check_packed_layout(
+    index_size: usize,
+    indices_per_u64: u8,
+    mask: u64,
+    len: usize,
+    storage: &[u64],
+) -> Result<(), InvariantError> {
+    if index_size == 0 {
+        // The other fields are left as they were when shrinking to 0
+        if !storage.is_empty() {
+            return Err(InvariantError::InconsistentField("storage"));
+        }
+        return Ok(());
+    }
+    let expected_indices_per_u64 = 64 / index_size;
+    if indices_per_u64 as usize != expected_indices_per_u64 {
+        return Err(InvariantError::InconsistentField("indices_per_u64"));
+    }
+    if mask != u64::MAX >> (64 - index_size) {
+        return Err(InvariantError::InconsistentField("mask"));
+    }
+    let expected = len.div_ceil(expected_indices_per_u64);
+    if storage.len() < expected {
+        return Err(InvariantError::StorageTooShort {
+            expected,
+            found: storage.len(),
+        });
+    }
+    // Pushing appends whole words, so trailing words would misplace new indices
+    if storage.len() > expected {
+        return Err(InvariantError::InconsistentField("storage"));
+    }
+    Ok(())
+}
+
 pub trait IndexBuffer {
     fn new() -> Self;
     /// Clears itself and fills itself with len 0-indices.
@@ -25,6 +65,11 @@ pub trait IndexBuffer {
     /// Returns a mapping of removed indices to their count and the amount of
     /// the new index added
     fn resize(&mut self, new_len: usize, index: usize) -> (Option<FxHashMap<usize, CountType>>, Option<CountType>);
+    /// Shortens the index buffer to new_len, dropping whole words at once.
+    /// Returns a mapping of removed indices to their count.
+    ///
+    /// new_len has to be <= len().
+    fn truncate(&mut self, new_len: usize) -> FxHashMap<usize, CountType>;
     
     /// Returns the number of indices in the buffer.
     fn len(&self) -> usize;
@@ -35,6 +80,17 @@ pub trait IndexBuffer {
     ///
     /// ALLOWED INDEX SIZES: [0, 63]
     fn set_index_size(&mut self, new_size: usize, new_mapping: Option<FxHashMap<usize, usize>>);
+    /// Returns the amount of bits every stored index occupies.
+    /// This can be bigger than the index size passed to set_index_size().
+    fn bits_per_index(&self) -> usize;
+    /// Returns the raw storage. Every u64 holds 64 / bits_per_index() indices,
+    /// starting at the least significant bits.
+    ///
+    /// IMPORTANT: Unused bits and slots past len() may contain garbage.
+    fn packed_words(&self) -> &[u64];
+    /// Checks the internal structure, for example after deserializing untrusted data.
+    /// Must not panic, no matter the field values.
+    fn check_invariants(&self) -> Result<(), InvariantError>;
 
     /// index_offset in indices, not bits
     /// returns the old index
@@ -43,14 +99,50 @@ pub trait IndexBuffer {
     ///
     /// Out of bounds access is checked in palettevec
     fn get_index(&self, index_offset: usize) -> usize;
+    /// Overwrites all indices in the range with index, writing whole u64 words
+    /// of the repeated pattern where possible.
+    /// Returns a mapping of overwritten indices to their count.
+    ///
+    /// Out of bounds access is checked in palettevec
+    fn fill_range(&mut self, range: Range<usize>, index: usize) -> FxHashMap<usize, CountType>;
+    /// Replaces every occurrence of the index from with the index to in a single pass.
+    fn replace_index(&mut self, from: usize, to: usize);
 
     fn push_index(&mut self, index: usize);
     fn pop_index(&mut self) -> Option<usize>;
+    /// Appends all indices at once, writing whole u64 words where possible.
+    ///
+    /// The index size has to be big enough for all indices beforehand.
+    fn extend_indices(&mut self, indices: &[usize]);
+    /// Appends len indices packed with bits per index, in the layout of packed_words().
+    /// Copies whole words where the layouts match.
+    ///
+    /// The index size has to be big enough for all indices beforehand, and bits > 0.
+    fn extend_packed(&mut self, words: &[u64], bits: usize, len: usize);
+    /// Inserts the index at index_offset, shifting all following indices up by one.
+    ///
+    /// index_offset == len() is allowed and behaves like push_index.
+    fn insert_index(&mut self, index_offset: usize, index: usize);
+    /// Removes the index at index_offset, shifting all following indices down by one.
+    /// Returns the removed index.
+    ///
+    /// Out of bounds access is checked in palettevec
+    fn remove_index(&mut self, index_offset: usize) -> usize;
+    /// Swaps the indices at both offsets.
+    ///
+    /// Out of bounds access is checked in palettevec
+    fn swap_indices(&mut self, a: usize, b: usize);
+    /// Reverses the order of all indices in place.
+    fn reverse_indices(&mut self);
 
     // INDEX ITERATOR
-    type Iter<'a>: Iterator<Item = usize>
+    type Iter<'a>: DoubleEndedIterator<Item = usize> + ExactSizeIterator + FusedIterator
     where
         Self: 'a;
 
     fn iter(&self) -> Self::Iter<'_>;
+    /// Iterates over the indices in the range only.
+    ///
+    /// Out of bounds access is checked in palettevec
+    fn iter_range(&self, range: Range<usize>) -> Self::Iter<'_>;
 }
@@ -0,0 +1,281 @@
+//! Word-level helpers shared by the index buffers.
+//!
+//! Both `AlignedIndexBuffer` and `FastIndexBuffer` store `64 / bits` indices
+//! per u64, starting at the least significant bits, and never let an index
+//! cross a u64 boundary. Bits above the last index of a word and slots past
+//! the end of the buffer may contain garbage.
+
+use std::ops::Range;
+
+use rustc_hash::FxHashMap;
+
+use crate::palette::CountType;
+
+pub(crate) fn get(storage: &[u64], bits: usize, offset: usize) -> usize {
+    debug_assert!(bits > 0);
+    let indices_per_u64 = 64 / bits;
+    let mask = u64::MAX >> (64 - bits);
+    let shift = (offset % indices_per_u64) * bits;
+    ((storage[offset / indices_per_u64] >> shift) & mask) as usize
+}
+
+/// Returns the old index
+pub(crate) fn set(storage: &mut [u64], bits: usize, offset: usize, index: usize) -> usize {
+    debug_assert!(bits > 0);
+    let indices_per_u64 = 64 / bits;
+    let mask = u64::MAX >> (64 - bits);
+    let shift = (offset % indices_per_u64) * bits;
+    let word = &mut storage[offset / indices_per_u64];
+    let old_index = (*word >> shift) & mask;
+    *word = (*word & !(mask << shift)) | ((index as u64) << shift);
+    old_index as usize
+}
+
+pub(crate) fn swap(storage: &mut [u64], bits: usize, a: usize, b: usize) {
+    let index_a = get(storage, bits, a);
+    let index_b = set(storage, bits, b, index_a);
+    set(storage, bits, a, index_b);
+}
+
+pub(crate) fn reverse(storage: &mut [u64], bits: usize, len: usize) {
+    if len < 2 {
+        return;
+    }
+    let (mut front, mut back) = (0, len - 1);
+    while front < back {
+        swap(storage, bits, front, back);
+        front += 1;
+        back -= 1;
+    }
+}
+
+/// Overwrites all indices in `range` with `index`. Words that are completely
+/// covered by the range get the repeated pattern written at once.
+/// Returns a mapping of overwritten indices to their count.
+pub(crate) fn fill(
+    storage: &mut [u64],
+    bits: usize,
+    range: Range<usize>,
+    index: usize,
+) -> FxHashMap<usize, CountType> {
+    debug_assert!(bits > 0);
+    let indices_per_u64 = 64 / bits;
+    let mask = u64::MAX >> (64 - bits);
+    let used = u64::MAX >> (64 - indices_per_u64 * bits);
+    let pattern = (0..indices_per_u64).fold(0, |word, slot| word | ((index as u64) << (slot * bits)));
+
+    let mut overwritten = FxHashMap::default();
+    let mut offset = range.start;
+    while offset < range.end && !offset.is_multiple_of(indices_per_u64) {
+        *overwritten.entry(set(storage, bits, offset, index)).or_insert(0) += 1;
+        offset += 1;
+    }
+    while offset + indices_per_u64 <= range.end {
+        let word = &mut storage[offset / indices_per_u64];
+        if *word & used == pattern {
+            *overwritten.entry(index).or_insert(0) += indices_per_u64 as CountType;
+        } else {
+            for slot in 0..indices_per_u64 {
+                let old_index = ((*word >> (slot * bits)) & mask) as usize;
+                *overwritten.entry(old_index).or_insert(0) += 1;
+            }
+        }
+        *word = pattern;
+        offset += indices_per_u64;
+    }
+    while offset < range.end {
+        *overwritten.entry(set(storage, bits, offset, index)).or_insert(0) += 1;
+        offset += 1;
+    }
+    overwritten
+}
+
+/// Calls `f` with each of the first `len` indices in order, decoding a whole word at a time.
+pub(crate) fn for_each(storage: &[u64], bits: usize, len: usize, mut f: impl FnMut(usize)) {
+    debug_assert!(bits > 0);
+    let indices_per_u64 = 64 / bits;
+    let mask = u64::MAX >> (64 - bits);
+    let words = len.div_ceil(indices_per_u64);
+    for (i, word) in storage[..words].iter().enumerate() {
+        let slots = (len - i * indices_per_u64).min(indices_per_u64);
+        for slot in 0..slots {
+            f(((word >> (slot * bits)) & mask) as usize);
+        }
+    }
+}
+
+/// Zeroes the unused bits of every word and all slots past `len`.
+pub(crate) fn clear_unused(storage: &mut [u64], bits: usize, len: usize) {
+    debug_assert!(bits > 0);
+    let indices_per_u64 = 64 / bits;
+    let used = u64::MAX >> (64 - indices_per_u64 * bits);
+    let words = len.div_ceil(indices_per_u64);
+    for word in storage[..words].iter_mut() {
+        *word &= used;
+    }
+    for word in storage[words..].iter_mut() {
+        *word = 0;
+    }
+    let rest = len % indices_per_u64;
+    if rest != 0 {
+        storage[words - 1] &= u64::MAX >> (64 - rest * bits);
+    }
+}
+
+/// Compares the first `len` indices of two storages with the same layout,
+/// a whole word at a time. Garbage bits are masked out.
+pub(crate) fn eq(a: &[u64], b: &[u64], bits: usize, len: usize) -> bool {
+    debug_assert!(bits > 0);
+    let indices_per_u64 = 64 / bits;
+    let used = u64::MAX >> (64 - indices_per_u64 * bits);
+    let full_words = len / indices_per_u64;
+    if a[..full_words]
+        .iter()
+        .zip(&b[..full_words])
+        .any(|(a, b)| (a ^ b) & used != 0)
+    {
+        return false;
+    }
+    let rest = len % indices_per_u64;
+    if rest == 0 {
+        return true;
+    }
+    let rest_mask = u64::MAX >> (64 - rest * bits);
+    (a[full_words] ^ b[full_words]) & rest_mask == 0
+}
+
+/// Replaces every occurrence of `from` with `to` within the first `len` indices.
+pub(crate) fn replace(storage: &mut [u64], bits: usize, len: usize, from: usize, to: usize) {
+    debug_assert!(bits > 0);
+    let indices_per_u64 = 64 / bits;
+    let mask = u64::MAX >> (64 - bits);
+    let (from, to) = (from as u64, to as u64);
+    let words = len.div_ceil(indices_per_u64);
+    for (i, word) in storage[..words].iter_mut().enumerate() {
+        let slots = (len - i * indices_per_u64).min(indices_per_u64);
+        for slot in 0..slots {
+            let shift = slot * bits;
+            if (*word >> shift) & mask == from {
+                *word = (*word & !(mask << shift)) | (to << shift);
+            }
+        }
+    }
+}
+
+/// Appends `indices` behind the first `len` indices. The last partially filled
+/// word gets topped up first, everything after that is written as whole words.
+pub(crate) fn extend(storage: &mut Vec<u64>, bits: usize, len: usize, indices: &[usize]) {
+    debug_assert!(bits > 0);
+    let indices_per_u64 = 64 / bits;
+    let free_slots = (indices_per_u64 - len % indices_per_u64) % indices_per_u64;
+    let (head, tail) = indices.split_at(free_slots.min(indices.len()));
+    for (i, index) in head.iter().enumerate() {
+        set(storage, bits, len + i, *index);
+    }
+    storage.reserve(tail.len().div_ceil(indices_per_u64));
+    for chunk in tail.chunks(indices_per_u64) {
+        let mut word = 0;
+        for (slot, index) in chunk.iter().enumerate() {
+            word |= (*index as u64) << (slot * bits);
+        }
+        storage.push(word);
+    }
+}
+
+/// Appends the first `src_len` indices of `src`, packed with `src_bits` each, behind
+/// the first `len` indices. Whole words are copied when both layouts line up.
+pub(crate) fn extend_packed(
+    storage: &mut Vec<u64>,
+    bits: usize,
+    len: usize,
+    src: &[u64],
+    src_bits: usize,
+    src_len: usize,
+) {
+    debug_assert!(bits > 0 && src_bits > 0);
+    let indices_per_u64 = 64 / bits;
+    if bits == src_bits && len.is_multiple_of(indices_per_u64) {
+        storage.truncate(len / indices_per_u64);
+        storage.extend_from_slice(&src[..src_len.div_ceil(indices_per_u64)]);
+        return;
+    }
+    let mut chunk = Vec::with_capacity(src_len.min(1024));
+    let mut len = len;
+    for_each(src, src_bits, src_len, |index| {
+        chunk.push(index);
+        if chunk.len() == 1024 {
+            extend(storage, bits, len, &chunk);
+            len += chunk.len();
+            chunk.clear();
+        }
+    });
+    extend(storage, bits, len, &chunk);
+}
+
+/// Inserts `index` at `offset`, shifting all following indices up by one slot.
+/// Indices that fall off the top of a word are carried into the next word.
+///
+/// `len` is the amount of indices before the insertion.
+pub(crate) fn insert(storage: &mut Vec<u64>, bits: usize, len: usize, offset: usize, index: usize) {
+    debug_assert!(bits > 0);
+    debug_assert!(offset <= len);
+    let indices_per_u64 = 64 / bits;
+    if indices_per_u64 == 1 {
+        storage.insert(offset, index as u64);
+        return;
+    }
+    if len.is_multiple_of(indices_per_u64) {
+        storage.push(0);
+    }
+    let mask = u64::MAX >> (64 - bits);
+    let used = u64::MAX >> (64 - indices_per_u64 * bits);
+    let top_shift = (indices_per_u64 - 1) * bits;
+
+    let first = offset / indices_per_u64;
+    let slot_shift = (offset % indices_per_u64) * bits;
+    let low_mask = (1u64 << slot_shift) - 1;
+
+    let word = storage[first];
+    let mut carry = (word >> top_shift) & mask;
+    storage[first] =
+        ((word & low_mask) | ((index as u64) << slot_shift) | ((word & !low_mask) << bits)) & used;
+    for word in storage[first + 1..].iter_mut() {
+        let top = (*word >> top_shift) & mask;
+        *word = ((*word << bits) | carry) & used;
+        carry = top;
+    }
+}
+
+/// Removes the index at `offset`, shifting all following indices down by one slot.
+/// The lowest index of every following word is carried into the previous word.
+///
+/// `len` is the amount of indices before the removal. Returns the removed index.
+pub(crate) fn remove(storage: &mut Vec<u64>, bits: usize, len: usize, offset: usize) -> usize {
+    debug_assert!(bits > 0);
+    debug_assert!(offset < len);
+    let indices_per_u64 = 64 / bits;
+    if indices_per_u64 == 1 {
+        return storage.remove(offset) as usize;
+    }
+    let mask = u64::MAX >> (64 - bits);
+    let top_shift = (indices_per_u64 - 1) * bits;
+    let top_clear = !(mask << top_shift);
+
+    let first = offset / indices_per_u64;
+    let slot_shift = (offset % indices_per_u64) * bits;
+    let low_mask = (1u64 << slot_shift) - 1;
+
+    let word = storage[first];
+    let removed = (word >> slot_shift) & mask;
+    let next = storage.get(first + 1).map_or(0, |next| next & mask);
+    storage[first] =
+        (word & low_mask) | ((word >> bits) & !low_mask & top_clear) | (next << top_shift);
+    for i in first + 1..storage.len() {
+        let next = storage.get(i + 1).map_or(0, |next| next & mask);
+        storage[i] = ((storage[i] >> bits) & top_clear) | (next << top_shift);
+    }
+    if (len - 1).is_multiple_of(indices_per_u64) {
+        storage.pop();
+    }
+    removed as usize
+}
@@ -0,0 +1,220 @@
+//! Consistency checks for data that didn't go through the regular API,
+//! like deserialized chunks coming from untrusted sources.
+//!
+//! serde deserialization runs these checks automatically. The bitcode derive
+//! has no way to reject decoded data, so decode untrusted input with
+//! [`PaletteVec::decode_bitcode`] instead of `bitcode::decode`.
+use std::{fmt, hash::Hash};
+
+use crate::{
+    index_buffer::IndexBuffer,
+    palette::{count_to_usize, Palette},
+    PaletteVec,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvariantError {
+    /// The index size isn't supported by the palette or index buffer.
+    UnsupportedIndexSize(usize),
+    /// A field that is derived from other fields, like the mask of an
+    /// index buffer, doesn't match them.
+    InconsistentField(&'static str),
+    /// The storage of an index buffer is too short for its length.
+    StorageTooShort { expected: usize, found: usize },
+    /// The palette doesn't contain the amount of entries it claims to.
+    EntryCount { expected: usize, found: usize },
+    /// A palette entry has a count of 0.
+    ZeroCount { index: usize },
+    /// Two palette entries have the same value.
+    DuplicateValue { index: usize },
+    /// A palette entry sits at an index that doesn't fit into the index size.
+    IndexTooLarge { index: usize, index_size: usize },
+    /// The index buffer stores fewer bits per index than the palette needs.
+    IndexSizeMismatch { palette: usize, buffer: usize },
+    /// The index buffer references a palette index without an entry.
+    MissingEntry { offset: usize, index: usize },
+    /// The count of a palette entry doesn't match the amount of its indices
+    /// in the index buffer.
+    CountMismatch {
+        index: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantError::UnsupportedIndexSize(index_size) => {
+                write!(f, "index size {} is not supported", index_size)
+            }
+            InvariantError::InconsistentField(field) => {
+                write!(f, "field {} is inconsistent with the rest of the data", field)
+            }
+            InvariantError::StorageTooShort { expected, found } => write!(
+                f,
+                "index buffer needs at least {} words but has {}",
+                expected, found
+            ),
+            InvariantError::EntryCount { expected, found } => {
+                write!(f, "expected {} palette entries but found {}", expected, found)
+            }
+            InvariantError::ZeroCount { index } => {
+                write!(f, "palette entry {} has a count of 0", index)
+            }
+            InvariantError::DuplicateValue { index } => {
+                write!(f, "palette entry {} has the value of another entry", index)
+            }
+            InvariantError::IndexTooLarge { index, index_size } => write!(
+                f,
+                "palette index {} doesn't fit into an index size of {}",
+                index, index_size
+            ),
+            InvariantError::IndexSizeMismatch { palette, buffer } => write!(
+                f,
+                "palette index size {} doesn't fit into {} bits per index",
+                palette, buffer
+            ),
+            InvariantError::MissingEntry { offset, index } => write!(
+                f,
+                "index {} at offset {} has no palette entry",
+                index, offset
+            ),
+            InvariantError::CountMismatch {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "palette entry {} has a count of {} but is used {} times",
+                index, found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InvariantError {}
+
+/// Error of [`PaletteVec::decode_bitcode`].
+#[cfg(feature = "bitcode")]
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The bytes aren't a valid bitcode encoding of the type.
+    Bitcode(bitcode::Error),
+    /// The bytes decoded, but the data is inconsistent.
+    Invariant(InvariantError),
+}
+
+#[cfg(feature = "bitcode")]
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Bitcode(err) => write!(f, "invalid bitcode: {}", err),
+            DecodeError::Invariant(err) => write!(f, "invalid PaletteVec: {}", err),
+        }
+    }
+}
+
+#[cfg(feature = "bitcode")]
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::Bitcode(err) => Some(err),
+            DecodeError::Invariant(err) => Some(err),
+        }
+    }
+}
+
+#[cfg(feature = "bitcode")]
+impl From<bitcode::Error> for DecodeError {
+    fn from(err: bitcode::Error) -> Self {
+        DecodeError::Bitcode(err)
+    }
+}
+
+#[cfg(feature = "bitcode")]
+impl From<InvariantError> for DecodeError {
+    fn from(err: InvariantError) -> Self {
+        DecodeError::Invariant(err)
+    }
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteVec<T, P, B> {
+    /// Checks the palette and index buffer, and that the count of every palette
+    /// entry matches the amount of indices pointing to it.
+    ///
+    /// A `PaletteVec` built through the regular API always passes. This is meant
+    /// for data from untrusted sources, and never panics.
+    pub fn check_invariants(&self) -> Result<(), InvariantError> {
+        self.palette.check_invariants()?;
+        self.buffer.check_invariants()?;
+        let palette_bits = self.palette.index_size();
+        let buffer_bits = self.buffer.bits_per_index();
+        if buffer_bits < palette_bits {
+            return Err(InvariantError::IndexSizeMismatch {
+                palette: palette_bits,
+                buffer: buffer_bits,
+            });
+        }
+
+        let bound = self.palette.index_bound();
+        let present = (0..bound)
+            .map(|index| self.palette.get_by_index(index).is_some())
+            .collect::<Vec<_>>();
+        let mut counts = vec![0usize; bound];
+        if buffer_bits == 0 {
+            if !self.buffer.is_empty() {
+                if !present.first().copied().unwrap_or(false) {
+                    return Err(InvariantError::MissingEntry {
+                        offset: 0,
+                        index: 0,
+                    });
+                }
+                counts[0] = self.buffer.len();
+            }
+        } else {
+            let mut offset = 0;
+            let mut missing = None;
+            Self::for_each_index(&self.buffer, |index| {
+                if missing.is_none() {
+                    if present.get(index).copied().unwrap_or(false) {
+                        counts[index] += 1;
+                    } else {
+                        missing = Some(InvariantError::MissingEntry { offset, index });
+                    }
+                }
+                offset += 1;
+            });
+            if let Some(missing) = missing {
+                return Err(missing);
+            }
+        }
+
+        for (index, expected) in counts.into_iter().enumerate() {
+            let found = self
+                .palette
+                .get_by_index(index)
+                .map_or(0, |entry| count_to_usize(entry.count));
+            if found != expected {
+                return Err(InvariantError::CountMismatch {
+                    index,
+                    expected,
+                    found,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Decodes bitcode produced by `bitcode::encode` and checks the result with
+    /// [`PaletteVec::check_invariants`], so untrusted input can't cause panics later.
+    #[cfg(feature = "bitcode")]
+    pub fn decode_bitcode(bytes: &[u8]) -> Result<Self, DecodeError>
+    where
+        Self: bitcode::DecodeOwned,
+    {
+        let vec: Self = bitcode::decode(bytes)?;
+        vec.check_invariants()?;
+        Ok(vec)
+    }
+}
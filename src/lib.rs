@@ -15,15 +15,37 @@
 //!   - `B`: The `IndexBuffer` implementation (e.g., `AlignedIndexBuffer`).
 //! - **`Palette<T>` trait:** Defines the interface for palette implementations.
 //! - **`IndexBuffer` trait:** Defines the interface for how indices are stored.
-use std::{hash::Hash, marker::PhantomData, ops::Add};
-use std::ops::{Index};
+use std::{
+    borrow::{Borrow, Cow},
+    cmp::Ordering,
+    hash::Hash,
+    iter::FusedIterator,
+    marker::PhantomData,
+    ops::Add,
+};
+use std::ops::{Bound, Index, Range, RangeBounds};
 use index_buffer::IndexBuffer;
+use rustc_hash::FxHashMap;
 use palette::{Palette, PaletteEntry};
+use slice::{PaletteSlice, PaletteSliceMut};
 
-use crate::palette::CountType;
+use crate::palette::{count_to_usize, CountType};
 
+#[cfg(feature = "bitcode")]
+pub use invariant::DecodeError;
+pub use invariant::InvariantError;
+
+#[cfg(feature = "serde")]
+pub mod canonical;
 pub mod index_buffer;
+pub mod grid;
+pub mod invariant;
+pub mod minecraft;
+pub mod native;
 pub mod palette;
+pub mod schematic;
+pub mod slice;
+pub mod view;
 
 #[cfg(test)]
 pub(crate) mod tests;
@@ -55,8 +77,12 @@ impl Add for MemoryUsage {
 /// `T`: The type of elements stored. Must implement `Eq`, `Hash`, and `Clone`. \
 /// `P`: The `Palette` implementation used to manage unique elements. \
 /// `B`: The `IndexBuffer` implementation used to store indices into the palette.
+///
+/// With the `serde` feature, deserializing checks the data with
+/// [`PaletteVec::check_invariants`]. With the `bitcode` feature, use
+/// [`PaletteVec::decode_bitcode`] to get the same checks.
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(feature = "bitcode", derive(bitcode::Encode, bitcode::Decode))]
 pub struct PaletteVec<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> {
     palette: P,
@@ -64,6 +90,32 @@ pub struct PaletteVec<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> {
     phantom: PhantomData<T>,
 }
 
+#[cfg(feature = "serde")]
+impl<'de, T, P, B> serde::Deserialize<'de> for PaletteVec<T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T> + serde::Deserialize<'de>,
+    B: IndexBuffer + serde::Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "PaletteVec")]
+        struct Unchecked<T, P, B> {
+            palette: P,
+            buffer: B,
+            phantom: PhantomData<T>,
+        }
+        let unchecked = Unchecked::deserialize(deserializer)?;
+        let vec = Self {
+            palette: unchecked.palette,
+            buffer: unchecked.buffer,
+            phantom: unchecked.phantom,
+        };
+        vec.check_invariants().map_err(serde::de::Error::custom)?;
+        Ok(vec)
+    }
+}
+
 impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteVec<T, P, B> {
     pub fn new() -> Self {
         Self {
@@ -74,6 +126,10 @@ impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteVec<T, P, B> {
     }
 
     pub fn filled(value: T, len: usize) -> Self {
+        if len == 0 {
+            // The palette must not contain entries with a count of 0
+            return Self::new();
+        }
         let mut palette = P::new();
         let (index, index_size) = palette.insert_new(PaletteEntry {
             value,
@@ -105,6 +161,30 @@ impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteVec<T, P, B> {
         self.palette.len()
     }
 
+    /// Returns true if the vector contains an element equal to `value`.
+    ///
+    /// This only looks at the palette and never touches the index buffer.
+    pub fn contains(&self, value: &T) -> bool {
+        self.palette.get_by_value(value).is_some()
+    }
+
+    /// Returns how many elements are equal to `value`.
+    ///
+    /// This only looks at the palette and never touches the index buffer.
+    pub fn count_of(&self, value: &T) -> usize {
+        self.palette
+            .get_by_value(value)
+            .map_or(0, |(entry, _)| count_to_usize(entry.count))
+    }
+
+    /// Returns an iterator over all unique values and how often they occur,
+    /// in no particular order.
+    pub fn histogram(&self) -> PaletteVecHistogram<'_, T, P> {
+        PaletteVecHistogram {
+            entries: self.palette.iter(),
+        }
+    }
+
     /// Quickly estimates the memory used by the PaletteVec.
     ///
     /// IMPORTANT: Because of technical reasons, this is just an estimate,
@@ -147,6 +227,14 @@ impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteVec<T, P, B> {
         self.buffer.push_index(index);
     }
 
+    /// Appends all elements of the slice.
+    ///
+    /// The palette is updated first and the index buffer is resized at most once,
+    /// after which all indices get written as packed words.
+    pub fn extend_from_slice(&mut self, values: &[T]) {
+        self.extend(values);
+    }
+
     pub fn pop(&mut self) -> Option<T> {
         let index = self.buffer.pop_index()?;
         let entry = self.palette.get_mut_by_index(index)?;
@@ -158,6 +246,89 @@ impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteVec<T, P, B> {
         Some(value)
     }
 
+    /// Inserts an element at position `offset`, shifting all elements after it to the right.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset > len`.
+    pub fn insert(&mut self, offset: usize, value: &T) {
+        assert!(
+            offset <= self.len(),
+            "Insertion index {} out of bounds for PaletteVec with length {}",
+            offset,
+            self.len()
+        );
+        let index = self.acquire_index(value, 1);
+        self.buffer.insert_index(offset, index);
+    }
+
+    /// Removes and returns the element at position `offset`, shifting all elements
+    /// after it to the left.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset >= len`.
+    pub fn remove(&mut self, offset: usize) -> T {
+        assert!(
+            offset < self.len(),
+            "Removal index {} out of bounds for PaletteVec with length {}",
+            offset,
+            self.len()
+        );
+        let index = self.buffer.remove_index(offset);
+        let entry = self.palette.get_mut_by_index(index).unwrap();
+        entry.count -= 1;
+        let value = entry.value.clone();
+        if entry.count == 0 {
+            self.palette.mark_as_unused(index);
+        }
+        value
+    }
+
+    /// Removes the element at position `offset` and returns it. The removed element
+    /// is replaced by the last element, so this does not preserve ordering but is O(1).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset >= len`.
+    pub fn swap_remove(&mut self, offset: usize) -> T {
+        assert!(
+            offset < self.len(),
+            "Removal index {} out of bounds for PaletteVec with length {}",
+            offset,
+            self.len()
+        );
+        let last_offset = self.len() - 1;
+        if offset != last_offset {
+            self.buffer.swap_indices(offset, last_offset);
+        }
+        self.pop().unwrap()
+    }
+
+    /// Swaps two elements. Only the raw indices are moved, the palette is untouched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a` or `b` are out of bounds.
+    pub fn swap(&mut self, a: usize, b: usize) {
+        assert!(
+            a < self.len() && b < self.len(),
+            "Swap indices {} and {} out of bounds for PaletteVec with length {}",
+            a,
+            b,
+            self.len()
+        );
+        if a != b {
+            self.buffer.swap_indices(a, b);
+        }
+    }
+
+    /// Reverses the order of elements in place. Only the raw indices are moved,
+    /// the palette is untouched.
+    pub fn reverse(&mut self) {
+        self.buffer.reverse_indices();
+    }
+
     pub fn set(&mut self, offset: usize, value: &T) {
         let old_index_size = self.palette.index_size();
         // Check if the value is already in the palette
@@ -196,6 +367,167 @@ impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteVec<T, P, B> {
         }
     }
 
+    /// Overwrites every element with `value`. This resets the palette to a single
+    /// entry, so afterwards the index buffer does not use any memory.
+    pub fn fill(&mut self, value: T) {
+        let len = self.len();
+        self.clear();
+        if len == 0 {
+            return;
+        }
+        let (index, index_size) = self.palette.insert_new(PaletteEntry {
+            value,
+            count: len as CountType,
+        });
+        debug_assert_eq!(index, 0);
+        debug_assert_eq!(index_size, None);
+        self.buffer.zeroed(len);
+    }
+
+    /// Overwrites every element in `range` with `value`.
+    ///
+    /// The palette index is resolved once and the index buffer writes whole
+    /// u64 words of the repeated index where possible.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn fill_range(&mut self, range: impl RangeBounds<usize>, value: &T) {
+        let range = resolve_range(range, self.len());
+        if range.is_empty() {
+            return;
+        }
+        let index = self.acquire_index(value, range.len() as CountType);
+        let overwritten = self.buffer.fill_range(range, index);
+        self.release_indices(overwritten);
+    }
+
+    /// Overwrites the elements starting at `offset` with the given values.
+    ///
+    /// Runs of equal values only need a single palette lookup, and the index
+    /// buffer is resized at most once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset + values.len() > len`.
+    pub fn set_range(&mut self, offset: usize, values: &[T]) {
+        let end = offset.checked_add(values.len());
+        assert!(
+            end.is_some_and(|end| end <= self.len()),
+            "Range {}+{} out of bounds for PaletteVec with length {}",
+            offset,
+            values.len(),
+            self.len()
+        );
+        if values.is_empty() {
+            return;
+        }
+        let old_index_size = self.palette.index_size();
+        let mut runs = Vec::new();
+        for run in values.chunk_by(|a, b| a == b) {
+            let index = match self.palette.get_mut_by_value(&run[0]) {
+                Some((entry, index)) => {
+                    entry.count += run.len() as CountType;
+                    index
+                }
+                None => {
+                    self.palette
+                        .insert_new(PaletteEntry {
+                            value: run[0].clone(),
+                            count: run.len() as CountType,
+                        })
+                        .0
+                }
+            };
+            runs.push((index, run.len()));
+        }
+        let new_index_size = self.palette.index_size();
+        if new_index_size != old_index_size {
+            self.buffer.set_index_size(new_index_size, None);
+        }
+        if new_index_size == 0 {
+            // The only palette entry was overwritten with itself
+            self.release_indices([(0, values.len() as CountType)]);
+            return;
+        }
+
+        let mut overwritten = vec![0; self.palette.index_bound()];
+        let mut offset = offset;
+        for (index, len) in runs {
+            for offset in offset..offset + len {
+                overwritten[self.buffer.set_index(offset, index)] += 1;
+            }
+            offset += len;
+        }
+        self.release_indices(overwritten.into_iter().enumerate());
+    }
+
+    /// Sets the element at `offset` and returns the element it replaced.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset >= len`.
+    pub fn replace(&mut self, offset: usize, value: &T) -> T {
+        let len = self.len();
+        self.try_replace(offset, value).unwrap_or_else(|| {
+            panic!(
+                "Index {} out of bounds for PaletteVec with length {}",
+                offset, len
+            )
+        })
+    }
+
+    /// Sets the element at `offset` and returns the element it replaced,
+    /// or `None` if `offset` is out of bounds.
+    pub fn try_replace(&mut self, offset: usize, value: &T) -> Option<T> {
+        if offset >= self.len() {
+            return None;
+        }
+        let index = self.acquire_index(value, 1);
+        let old_index = if self.palette.index_size() == 0 {
+            // The only palette entry is the value itself
+            0
+        } else {
+            self.buffer.set_index(offset, index)
+        };
+        let old_entry = self.palette.get_mut_by_index(old_index).unwrap();
+        old_entry.count -= 1;
+        // Clone before marking as unused, which drops the entry
+        let old_value = old_entry.value.clone();
+        if old_entry.count == 0 {
+            self.palette.mark_as_unused(old_index);
+        }
+        Some(old_value)
+    }
+
+    /// Replaces every element equal to `old` with `new` and returns how many
+    /// elements were replaced.
+    ///
+    /// If `new` is not in the palette yet, the value is swapped in place and the
+    /// index buffer is not touched at all. Otherwise both palette entries are
+    /// merged and the indices are remapped in a single pass over the buffer.
+    pub fn replace_all(&mut self, old: &T, new: T) -> usize {
+        let Some((old_entry, old_index)) = self.palette.get_by_value(old) else {
+            return 0;
+        };
+        let amount = old_entry.count;
+        if *old == new {
+            return count_to_usize(amount);
+        }
+        match self.palette.get_mut_by_value(&new) {
+            None => {
+                self.palette.replace_value(old_index, new);
+            }
+            Some((new_entry, new_index)) => {
+                new_entry.count += amount;
+                self.palette.get_mut_by_index(old_index).unwrap().count = 0;
+                self.palette.mark_as_unused(old_index);
+                self.buffer.replace_index(old_index, new_index);
+            }
+        }
+        count_to_usize(amount)
+    }
+
     pub fn get(&self, offset: usize) -> Option<&T> {
         if offset >= self.buffer.len() {
             return None;
@@ -212,6 +544,49 @@ impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteVec<T, P, B> {
         self.buffer.clear();
     }
 
+    /// Removes the elements in `range` and returns an iterator over them.
+    ///
+    /// The elements and their palette counts are removed right away, even if the iterator
+    /// isn't fully consumed or is leaked. Values that are no longer used are moved out of
+    /// the palette instead of cloned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn drain(&mut self, range: impl RangeBounds<usize>) -> PaletteVecDrain<'_, T, P, B> {
+        let range = resolve_range(range, self.len());
+        let drained = self.buffer.iter_range(range.clone()).collect::<Vec<_>>();
+        let tail = self
+            .buffer
+            .iter_range(range.end..self.len())
+            .collect::<Vec<_>>();
+        self.buffer.truncate(range.start);
+        self.buffer.extend_indices(&tail);
+
+        let mut amounts: FxHashMap<usize, CountType> = FxHashMap::default();
+        for index in drained.iter() {
+            *amounts.entry(*index).or_insert(0) += 1;
+        }
+        let values = amounts
+            .into_iter()
+            .map(|(index, amount)| {
+                let entry = self.palette.get_mut_by_index(index).unwrap();
+                entry.count -= amount;
+                let value = if entry.count == 0 {
+                    self.palette.take_unused(index)
+                } else {
+                    entry.value.clone()
+                };
+                (index, (value, amount))
+            })
+            .collect();
+        PaletteVecDrain {
+            indices: drained.into_iter(),
+            values,
+            phantom: PhantomData,
+        }
+    }
+
     pub fn resize(&mut self, new_len: usize, value: &T) {
         if new_len == self.len() {
             return;
@@ -219,16 +594,7 @@ impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteVec<T, P, B> {
             self.clear();
             return;
         } else if new_len < self.len() {
-            let (Some(removed), None) = self.buffer.resize(new_len, 0) else {
-                unreachable!()
-            };
-            for (id, amount) in removed {
-                let entry = self.palette.get_mut_by_index(id).unwrap();
-                entry.count -= amount;
-                if entry.count == 0 {
-                    self.palette.mark_as_unused(id);
-                }
-            }
+            self.truncate(new_len);
         } else if new_len > self.len() {
             // Check if the value is already in the palette
             let index = if let Some((_, index)) = self.palette.get_mut_by_value(value) {
@@ -258,6 +624,136 @@ impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteVec<T, P, B> {
         assert_eq !(self.len(), new_len)
     }
 
+    /// Shortens the vector, keeping the first `len` elements.
+    /// Has no effect if `len` is greater or equal to the current length.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len() {
+            return;
+        }
+        let removed = self.buffer.truncate(len);
+        self.release_indices(removed);
+    }
+
+    /// Splits the vector into two at the given offset. Returns a new `PaletteVec`
+    /// containing the elements `[at, len)`, `self` keeps the elements `[0, at)`.
+    ///
+    /// The returned `PaletteVec` only contains the palette entries it actually uses.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > len`.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(
+            at <= self.len(),
+            "Split index {} out of bounds for PaletteVec with length {}",
+            at,
+            self.len()
+        );
+        if at == self.len() {
+            return Self::new();
+        }
+        let other = self.copy_range(at..self.len());
+        for (index, amount) in self.buffer.truncate(at) {
+            let entry = self.palette.get_mut_by_index(index).unwrap();
+            entry.count -= amount;
+            if entry.count == 0 {
+                self.palette.mark_as_unused(index);
+            }
+        }
+        other
+    }
+
+    /// Moves all elements of `other` to the end of `self`, leaving `other` empty.
+    ///
+    /// The palettes are merged once and the indices of `other` are remapped in bulk,
+    /// so the index buffer of `self` is resized at most once.
+    pub fn append(&mut self, other: &mut Self) {
+        if other.is_empty() {
+            return;
+        }
+        let old_index_size = self.palette.index_size();
+        let mapping = self.merge_palette(other);
+        let new_index_size = self.palette.index_size();
+        if new_index_size != old_index_size {
+            self.buffer.set_index_size(new_index_size, None);
+        }
+        let indices = other
+            .buffer
+            .iter()
+            .map(|index| mapping[index])
+            .collect::<Vec<_>>();
+        self.buffer.extend_indices(&indices);
+        other.clear();
+    }
+
+    /// Concatenates all given `PaletteVec`s into a new one.
+    ///
+    /// All palettes are merged before any indices are written,
+    /// so the index buffer is sized exactly once.
+    pub fn concat<I>(vecs: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Self>,
+    {
+        let vecs = vecs.into_iter().collect::<Vec<_>>();
+        let mut result = Self::new();
+        let mappings = vecs
+            .iter()
+            .map(|other| result.merge_palette(other.borrow()))
+            .collect::<Vec<_>>();
+        result
+            .buffer
+            .set_index_size(result.palette.index_size(), None);
+        for (other, mapping) in vecs.iter().zip(mappings) {
+            let indices = other
+                .borrow()
+                .buffer
+                .iter()
+                .map(|index| mapping[index])
+                .collect::<Vec<_>>();
+            result.buffer.extend_indices(&indices);
+        }
+        result
+    }
+
+    /// Sorts the elements in ascending order.
+    ///
+    /// Only the palette entries get compared, the index buffer is then rewritten
+    /// as one run per entry. This is O(n + k log k) for k unique values.
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp);
+    }
+
+    /// Sorts the elements with a comparator function. See [`PaletteVec::sort`].
+    ///
+    /// Elements that compare equal but hold different values end up grouped by value,
+    /// so unlike `slice::sort_by` their original order is not kept.
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let mut entries = self.palette_entries_by_index();
+        entries.sort_by(|(_, a), (_, b)| compare(&a.value, &b.value));
+        let runs = entries
+            .into_iter()
+            .map(|(index, entry)| (index, entry.count))
+            .collect::<Vec<_>>();
+        self.write_runs(runs);
+    }
+
+    /// Sorts the elements with a key extraction function, which is only
+    /// called for palette entries, never per element. See [`PaletteVec::sort_by`].
+    pub fn sort_by_key<K, F>(&mut self, mut f: F)
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        self.sort_by(|a, b| f(a).cmp(&f(b)));
+    }
+
     /// Optimizes the palette and indices vector. This is potentially very expensive
     /// and should be done sparingly, but it should be done at some point.
     ///
@@ -271,10 +767,162 @@ impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteVec<T, P, B> {
         self.buffer.set_index_size(new_index_size, mapping);
     }
 
-    pub fn iter(&self) -> PaletteVecIter<T, P, B> {
+    /// Maps every element into a new `PaletteVec` of another element type.
+    ///
+    /// `f` is called exactly once per palette entry, not once per element.
+    /// Entries that map to the same value are merged. The index buffer is cloned
+    /// and, if needed, remapped in a single pass.
+    pub fn map<U, P2, F>(&self, mut f: F) -> PaletteVec<U, P2, B>
+    where
+        U: Eq + Hash + Clone,
+        P2: Palette<U>,
+        B: Clone,
+        F: FnMut(&T) -> U,
+    {
+        let mut palette = P2::new();
+        let mut mapping = FxHashMap::default();
+        let mut needs_mapping = false;
+        for index in 0..self.palette.index_bound() {
+            let Some(entry) = self.palette.get_by_index(index) else {
+                continue;
+            };
+            let value = f(&entry.value);
+            let new_index = match palette.get_mut_by_value(&value) {
+                Some((new_entry, new_index)) => {
+                    new_entry.count += entry.count;
+                    new_index
+                }
+                None => {
+                    palette
+                        .insert_new(PaletteEntry {
+                            value,
+                            count: entry.count,
+                        })
+                        .0
+                }
+            };
+            needs_mapping |= new_index != index;
+            mapping.insert(index, new_index);
+        }
+
+        let new_index_size = palette.index_size();
+        let buffer = if palette.len() <= 1 {
+            // Every element maps to the same value, no indices needed at all
+            let mut buffer = B::new();
+            buffer.zeroed(self.len());
+            buffer
+        } else {
+            let mut buffer = self.buffer.clone();
+            if needs_mapping {
+                buffer.set_index_size(new_index_size, Some(mapping));
+            } else if new_index_size != self.palette.index_size() {
+                buffer.set_index_size(new_index_size, None);
+            }
+            buffer
+        };
+        PaletteVec {
+            palette,
+            buffer,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Copies the elements into another palette and index buffer implementation,
+    /// for example to promote a frequently accessed chunk to `FastIndexBuffer`.
+    ///
+    /// Palette entries keep their counts and indices, so the index words are
+    /// repacked directly.
+    pub fn convert<P2: Palette<T>, B2: IndexBuffer>(&self) -> PaletteVec<T, P2, B2> {
+        let mut palette = P2::new();
+        for (index, entry) in self.palette_entries_by_index() {
+            palette.insert_at(index, entry.clone());
+        }
+
+        let mut buffer = B2::new();
+        buffer.set_index_size(palette.index_size(), None);
+        let bits = self.buffer.bits_per_index();
+        if bits == 0 || buffer.bits_per_index() == 0 {
+            // At most one unique value
+            buffer.zeroed(self.len());
+        } else {
+            buffer.extend_packed(self.buffer.packed_words(), bits, self.len());
+        }
+        PaletteVec {
+            palette,
+            buffer,
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn iter(&self) -> PaletteVecIter<'_, T, P, B> {
         self.into_iter()
     }
 
+    /// Returns an iterator over the elements in `range` only.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn iter_range(&self, range: impl RangeBounds<usize>) -> PaletteVecIter<'_, T, P, B> {
+        PaletteVecIter {
+            palette: &self.palette,
+            buffer_iter: self.buffer.iter_range(resolve_range(range, self.len())),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Copies all elements into a `Vec`, decoding a whole u64 word of indices at a time.
+    pub fn to_vec(&self) -> Vec<T> {
+        let values = (0..self.palette.index_bound())
+            .map(|index| self.palette.get_by_index(index).map(|entry| &entry.value))
+            .collect::<Vec<_>>();
+        let mut vec = Vec::with_capacity(self.len());
+        Self::for_each_index(&self.buffer, |index| vec.push(values[index].unwrap().clone()));
+        vec
+    }
+
+    /// Converts into a `Vec`, decoding a whole u64 word of indices at a time.
+    /// The last occurrence of every value is moved out of the palette instead of cloned.
+    pub fn into_vec(mut self) -> Vec<T> {
+        let mut vec = Vec::with_capacity(self.len());
+        let palette = &mut self.palette;
+        Self::for_each_index(&self.buffer, |index| {
+            let entry = palette.get_mut_by_index(index).unwrap();
+            entry.count -= 1;
+            vec.push(if entry.count == 0 {
+                palette.take_unused(index)
+            } else {
+                entry.value.clone()
+            });
+        });
+        vec
+    }
+
+    /// Returns a borrowed view of the elements in `range` without copying them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> PaletteSlice<'_, T, P, B> {
+        PaletteSlice::new(self, range)
+    }
+
+    /// Returns a mutable view of the elements in `range` that can only write inside the range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn slice_mut(&mut self, range: impl RangeBounds<usize>) -> PaletteSliceMut<'_, T, P, B> {
+        PaletteSliceMut::new(self, range)
+    }
+
+    /// Returns an iterator over runs of equal elements as `(value, run_length)` pairs.
+    ///
+    /// Runs are detected by comparing palette indices, so `T` is never compared.
+    pub fn iter_runs(&self) -> PaletteVecRuns<'_, T, P, B> {
+        PaletteVecRuns::new(&self.palette, self.buffer.iter())
+    }
+
     pub fn iter_palette_entries(&self) -> P::EntriesIter<'_> {
         self.palette.iter()
     }
@@ -287,51 +935,662 @@ impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteVec<T, P, B> {
     pub fn iter_palette_entries_mut(&mut self) -> P::EntriesIterMut<'_> {
         self.palette.iter_mut()
     }
-}
-
-impl <T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> Index<usize> for PaletteVec<T,P,B> {
-    type Output = T;
 
-    fn index(&self, index: usize) -> &Self::Output {
-        self.get(index).unwrap_or_else(|| panic!("Index {} out of bounds for PaletteVec with length {}", index, self.len()))
+    /// Subtracts the amounts from the counts of the given palette indices,
+    /// marking entries as unused once their count reaches 0.
+    fn release_indices(&mut self, amounts: impl IntoIterator<Item = (usize, CountType)>) {
+        for (index, amount) in amounts {
+            if amount == 0 {
+                continue;
+            }
+            let entry = self.palette.get_mut_by_index(index).unwrap();
+            entry.count -= amount;
+            if entry.count == 0 {
+                self.palette.mark_as_unused(index);
+            }
+        }
     }
-}
 
-impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> Default for PaletteVec<T, P, B> {
-    fn default() -> Self {
-        Self::new()
+    /// Releases one occurrence of the palette entry at `index` and returns its value.
+    /// The value is moved out of the palette if this was its last occurrence.
+    fn take_value(&mut self, index: usize) -> T {
+        let entry = self.palette.get_mut_by_index(index).unwrap();
+        entry.count -= 1;
+        if entry.count == 0 {
+            self.palette.take_unused(index)
+        } else {
+            entry.value.clone()
+        }
     }
-}
 
-// ITERATOR
-#[derive(Debug, Clone)]
-pub struct PaletteVecIter<'a, T, P, B>
-where
-    T: Eq + Hash + Clone,
-    P: Palette<T> + 'a,
-    B: IndexBuffer + 'a,
-{
-    palette: &'a P,
-    buffer_iter: B::Iter<'a>,
-    phantom: PhantomData<&'a T>,
-}
-
-impl<'a, T, P, B> Iterator for PaletteVecIter<'a, T, P, B>
-where
-    T: Eq + Hash + Clone,
-    P: Palette<T> + 'a,
-    B: IndexBuffer + 'a,
-{
-    type Item = &'a T;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let idx = self.buffer_iter.next()?;
-        let entry = self.palette.get_by_index(idx).unwrap();
-        Some(&entry.value)
+    /// Adds all palette entries of `other` to our palette, including their counts.
+    /// Returns a remap table from the indices of `other` to our indices.
+    ///
+    /// The index buffer is NOT resized, that is up to the caller.
+    fn merge_palette(&mut self, other: &Self) -> Vec<usize> {
+        let mut mapping = vec![0; other.palette.index_bound()];
+        for (index, new_index) in mapping.iter_mut().enumerate() {
+            let Some(entry) = other.palette.get_by_index(index) else {
+                continue;
+            };
+            *new_index = match self.palette.get_mut_by_value(&entry.value) {
+                Some((own_entry, own_index)) => {
+                    own_entry.count += entry.count;
+                    own_index
+                }
+                None => self.palette.insert_new(entry.clone()).0,
+            };
+        }
+        mapping
     }
-}
 
-impl<'a, T, P, B> IntoIterator for &'a PaletteVec<T, P, B>
+    /// Collects the palette indices of all values before touching the index buffer,
+    /// so it gets resized at most once no matter how many new values show up.
+    fn extend_cow<'a, I>(&mut self, values: I)
+    where
+        I: Iterator<Item = Cow<'a, T>>,
+        T: 'a,
+    {
+        let old_index_size = self.palette.index_size();
+        let mut indices = Vec::with_capacity(values.size_hint().0);
+        for value in values {
+            let index = match self.palette.get_mut_by_value(&value) {
+                Some((entry, index)) => {
+                    entry.count += 1;
+                    index
+                }
+                None => {
+                    self.palette
+                        .insert_new(PaletteEntry {
+                            value: value.into_owned(),
+                            count: 1,
+                        })
+                        .0
+                }
+            };
+            indices.push(index);
+        }
+        let new_index_size = self.palette.index_size();
+        if new_index_size != old_index_size {
+            self.buffer.set_index_size(new_index_size, None);
+        }
+        self.buffer.extend_indices(&indices);
+    }
+
+    /// Copies the elements in `range` into a new `PaletteVec` that only contains
+    /// the palette entries used within it. The new palette is built in ascending
+    /// index order and all indices are remapped once.
+    fn copy_range(&self, range: Range<usize>) -> Self {
+        let indices = self.buffer.iter_range(range).collect::<Vec<_>>();
+        let mut counts: Vec<CountType> = vec![0; self.palette.index_bound()];
+        for index in indices.iter() {
+            counts[*index] += 1;
+        }
+
+        let mut other = Self::new();
+        let mut mapping = vec![0; counts.len()];
+        for (index, count) in counts.into_iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let value = self.palette.get_by_index(index).unwrap().value.clone();
+            mapping[index] = other.palette.insert_new(PaletteEntry { value, count }).0;
+        }
+        other
+            .buffer
+            .set_index_size(other.palette.index_size(), None);
+        let indices = indices.into_iter().map(|index| mapping[index]).collect::<Vec<_>>();
+        other.buffer.extend_indices(&indices);
+        other
+    }
+
+    /// Returns all palette entries together with their index.
+    fn palette_entries_by_index(&self) -> Vec<(usize, &PaletteEntry<T>)> {
+        (0..self.palette.index_bound())
+            .filter_map(|index| self.palette.get_by_index(index).map(|entry| (index, entry)))
+            .collect()
+    }
+
+    /// Overwrites the whole index buffer with the given `(index, length)` runs.
+    /// The lengths have to add up to len() and the palette counts stay untouched.
+    fn write_runs(&mut self, runs: Vec<(usize, CountType)>) {
+        if self.palette.index_size() == 0 {
+            // Nothing to rewrite with at most one unique value
+            return;
+        }
+        let len = self.len();
+        self.buffer.zeroed(0);
+        self.extend_runs(runs.into_iter().map(|(index, length)| (index, count_to_usize(length))));
+        debug_assert_eq!(self.buffer.len(), len);
+    }
+
+    /// Appends `(index, length)` runs to the index buffer, without touching the palette.
+    fn extend_runs(&mut self, runs: impl IntoIterator<Item = (usize, usize)>) {
+        const CHUNK: usize = 1024;
+        let mut chunk = Vec::new();
+        for (index, mut remaining) in runs {
+            while remaining > 0 {
+                let amount = remaining.min(CHUNK);
+                chunk.clear();
+                chunk.resize(amount, index);
+                self.buffer.extend_indices(&chunk);
+                remaining -= amount;
+            }
+        }
+    }
+
+    /// Builds a `PaletteVec` from indices into a dense list of values.
+    /// Values that are never referenced are skipped and duplicate values are merged.
+    ///
+    /// Every index has to be smaller than `values.len()`.
+    fn from_dense_parts(values: &[T], mut indices: Vec<usize>) -> Self {
+        let mut counts: Vec<CountType> = vec![0; values.len()];
+        for index in indices.iter() {
+            counts[*index] += 1;
+        }
+        let (mut result, mapping) = Self::from_dense_counts(values, counts);
+        for index in indices.iter_mut() {
+            *index = mapping[*index];
+        }
+        result.buffer.extend_indices(&indices);
+        result
+    }
+
+    /// Creates an empty `PaletteVec` whose palette holds every value with a count above 0.
+    /// Returns it with the palette index of every value, the buffer is left for the caller to fill.
+    fn from_dense_counts(values: &[T], counts: Vec<CountType>) -> (Self, Vec<usize>) {
+        let mut result = Self::new();
+        let mut mapping = vec![0; values.len()];
+        for (dense_index, count) in counts.into_iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let value = &values[dense_index];
+            mapping[dense_index] = match result.palette.get_mut_by_value(value) {
+                Some((entry, index)) => {
+                    entry.count += count;
+                    index
+                }
+                None => {
+                    result
+                        .palette
+                        .insert_new(PaletteEntry {
+                            value: value.clone(),
+                            count,
+                        })
+                        .0
+                }
+            };
+        }
+        result
+            .buffer
+            .set_index_size(result.palette.index_size(), None);
+        (result, mapping)
+    }
+
+    /// Calls `f` with every index of the buffer in order.
+    fn for_each_index(buffer: &B, mut f: impl FnMut(usize)) {
+        let bits = buffer.bits_per_index();
+        if bits == 0 {
+            (0..buffer.len()).for_each(|_| f(0));
+        } else {
+            index_buffer::packed::for_each(buffer.packed_words(), bits, buffer.len(), f);
+        }
+    }
+
+    /// Returns the palette index of `value` and adds `amount` to its count.
+    /// If the value is new, it gets inserted and the index buffer grows if needed.
+    fn acquire_index(&mut self, value: &T, amount: CountType) -> usize {
+        if let Some((entry, index)) = self.palette.get_mut_by_value(value) {
+            entry.count += amount;
+            return index;
+        }
+        let (index, new_index_size) = self.palette.insert_new(PaletteEntry {
+            value: value.clone(),
+            count: amount,
+        });
+        if let Some(new_index_size) = new_index_size {
+            self.buffer.set_index_size(new_index_size, None);
+        }
+        index
+    }
+}
+
+/// Converts any range into a `Range` within `0..len`.
+///
+/// Panics like slice indexing does if the range is out of bounds.
+fn resolve_range(range: impl RangeBounds<usize>, len: usize) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(start) => *start,
+        Bound::Excluded(start) => start
+            .checked_add(1)
+            .expect("Range start overflowed usize"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(end) => end.checked_add(1).expect("Range end overflowed usize"),
+        Bound::Excluded(end) => *end,
+        Bound::Unbounded => len,
+    };
+    assert!(
+        start <= end,
+        "Range starts at {} but ends at {}",
+        start,
+        end
+    );
+    assert!(
+        end <= len,
+        "Range end {} out of bounds for PaletteVec with length {}",
+        end,
+        len
+    );
+    start..end
+}
+
+/// Two `PaletteVec`s are equal if they contain the same elements in the same order,
+/// no matter their palette order, index size or backends.
+impl<T, P, B, P2, B2> PartialEq<PaletteVec<T, P2, B2>> for PaletteVec<T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T>,
+    B: IndexBuffer,
+    P2: Palette<T>,
+    B2: IndexBuffer,
+{
+    fn eq(&self, other: &PaletteVec<T, P2, B2>) -> bool {
+        if self.len() != other.len() || self.unique_values() != other.unique_values() {
+            return false;
+        }
+        if self.is_empty() {
+            return true;
+        }
+
+        // Fast path: identical palettes and layouts allow comparing whole words
+        let bound = self.palette.index_bound().max(other.palette.index_bound());
+        let same_palette = (0..bound).all(|index| {
+            let ours = (index < self.palette.index_bound())
+                .then(|| self.palette.get_by_index(index))
+                .flatten();
+            let theirs = (index < other.palette.index_bound())
+                .then(|| other.palette.get_by_index(index))
+                .flatten();
+            match (ours, theirs) {
+                (Some(ours), Some(theirs)) => ours.value == theirs.value,
+                (None, None) => true,
+                _ => false,
+            }
+        });
+        if same_palette {
+            let bits = self.buffer.bits_per_index();
+            if bits == 0 || other.buffer.bits_per_index() == 0 {
+                // At most one palette entry, so every element is the same
+                return true;
+            }
+            if bits == other.buffer.bits_per_index() {
+                return index_buffer::packed::eq(
+                    self.buffer.packed_words(),
+                    other.buffer.packed_words(),
+                    bits,
+                    self.len(),
+                );
+            }
+        }
+
+        // Translate our indices into theirs once, then only compare indices
+        let mut mapping = vec![None; self.palette.index_bound()];
+        for (index, mapped) in mapping.iter_mut().enumerate() {
+            let Some(entry) = self.palette.get_by_index(index) else {
+                continue;
+            };
+            let Some((_, other_index)) = other.palette.get_by_value(&entry.value) else {
+                return false;
+            };
+            *mapped = Some(other_index);
+        }
+        self.buffer
+            .iter()
+            .zip(other.buffer.iter())
+            .all(|(ours, theirs)| mapping[ours] == Some(theirs))
+    }
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> Eq for PaletteVec<T, P, B> {}
+
+/// Hashes the logical element sequence, so equal `PaletteVec`s with different
+/// palette orders or backends hash the same. Runs are hashed as `(value, length)`.
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> Hash for PaletteVec<T, P, B> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for (value, length) in self.iter_runs() {
+            value.hash(state);
+            length.hash(state);
+        }
+    }
+}
+
+/// Compares the elements lexicographically, like slices do.
+impl<T, P, B, P2, B2> PartialOrd<PaletteVec<T, P2, B2>> for PaletteVec<T, P, B>
+where
+    T: Eq + Hash + Clone + PartialOrd,
+    P: Palette<T>,
+    B: IndexBuffer,
+    P2: Palette<T>,
+    B2: IndexBuffer,
+{
+    fn partial_cmp(&self, other: &PaletteVec<T, P2, B2>) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<T: Eq + Hash + Clone + Ord, P: Palette<T>, B: IndexBuffer> Ord for PaletteVec<T, P, B> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl <T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> Index<usize> for PaletteVec<T,P,B> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).unwrap_or_else(|| panic!("Index {} out of bounds for PaletteVec with length {}", index, self.len()))
+    }
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> Default for PaletteVec<T, P, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> FromIterator<T> for PaletteVec<T, P, B> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut palette_vec = Self::new();
+        palette_vec.extend(iter);
+        palette_vec
+    }
+}
+
+/// See `From<&[T]>`.
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> From<Vec<T>> for PaletteVec<T, P, B> {
+    fn from(values: Vec<T>) -> Self {
+        let mut palette_vec = Self::new();
+        palette_vec.extend_cow(values.into_iter().map(Cow::Owned));
+        palette_vec
+    }
+}
+
+/// Builds the palette from all distinct values first, then writes all indices
+/// at the final index size at once.
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> From<&[T]> for PaletteVec<T, P, B> {
+    fn from(values: &[T]) -> Self {
+        let mut palette_vec = Self::new();
+        palette_vec.extend_cow(values.iter().map(Cow::Borrowed));
+        palette_vec
+    }
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> From<PaletteVec<T, P, B>> for Vec<T> {
+    fn from(palette_vec: PaletteVec<T, P, B>) -> Self {
+        palette_vec.into_vec()
+    }
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> Extend<T> for PaletteVec<T, P, B> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.extend_cow(iter.into_iter().map(Cow::Owned));
+    }
+}
+
+impl<'a, T: Eq + Hash + Clone + 'a, P: Palette<T>, B: IndexBuffer> Extend<&'a T>
+    for PaletteVec<T, P, B>
+{
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend_cow(iter.into_iter().map(Cow::Borrowed));
+    }
+}
+
+// ITERATOR
+#[derive(Debug, Clone)]
+pub struct PaletteVecIter<'a, T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T> + 'a,
+    B: IndexBuffer + 'a,
+{
+    palette: &'a P,
+    buffer_iter: B::Iter<'a>,
+    phantom: PhantomData<&'a T>,
+}
+
+impl<'a, T, P, B> Iterator for PaletteVecIter<'a, T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T> + 'a,
+    B: IndexBuffer + 'a,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let idx = self.buffer_iter.next()?;
+        let entry = self.palette.get_by_index(idx).unwrap();
+        Some(&entry.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.buffer_iter.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        let idx = self.buffer_iter.nth(n)?;
+        Some(&self.palette.get_by_index(idx).unwrap().value)
+    }
+}
+
+impl<'a, T, P, B> DoubleEndedIterator for PaletteVecIter<'a, T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T> + 'a,
+    B: IndexBuffer + 'a,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let idx = self.buffer_iter.next_back()?;
+        Some(&self.palette.get_by_index(idx).unwrap().value)
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        let idx = self.buffer_iter.nth_back(n)?;
+        Some(&self.palette.get_by_index(idx).unwrap().value)
+    }
+}
+
+impl<'a, T, P, B> ExactSizeIterator for PaletteVecIter<'a, T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T> + 'a,
+    B: IndexBuffer + 'a,
+{
+}
+
+impl<'a, T, P, B> FusedIterator for PaletteVecIter<'a, T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T> + 'a,
+    B: IndexBuffer + 'a,
+{
+}
+
+// RUNS
+#[derive(Debug, Clone)]
+pub struct PaletteVecRuns<'a, T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T> + 'a,
+    B: IndexBuffer + 'a,
+{
+    palette: &'a P,
+    buffer_iter: B::Iter<'a>,
+    // Indices that were read past the end of a run and belong to the next one
+    front: Option<usize>,
+    back: Option<usize>,
+    phantom: PhantomData<&'a T>,
+}
+
+impl<'a, T, P, B> PaletteVecRuns<'a, T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T> + 'a,
+    B: IndexBuffer + 'a,
+{
+    fn new(palette: &'a P, buffer_iter: B::Iter<'a>) -> Self {
+        Self {
+            palette,
+            buffer_iter,
+            front: None,
+            back: None,
+            phantom: PhantomData,
+        }
+    }
+
+    fn remaining(&self) -> usize {
+        self.buffer_iter.len() + self.front.is_some() as usize + self.back.is_some() as usize
+    }
+
+    /// With an index size of 0 every element is the same, so everything left
+    /// is consumed as one run without scanning.
+    fn take_uniform_run(&mut self) -> Option<(&'a T, usize)> {
+        let remaining = self.remaining();
+        if remaining == 0 {
+            return None;
+        }
+        self.front = None;
+        self.back = None;
+        self.buffer_iter.nth(remaining);
+        Some(self.run(0, remaining))
+    }
+
+    fn run(&self, index: usize, length: usize) -> (&'a T, usize) {
+        (&self.palette.get_by_index(index).unwrap().value, length)
+    }
+}
+
+impl<'a, T, P, B> Iterator for PaletteVecRuns<'a, T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T> + 'a,
+    B: IndexBuffer + 'a,
+{
+    type Item = (&'a T, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.palette.index_size() == 0 {
+            return self.take_uniform_run();
+        }
+        let index = self
+            .front
+            .take()
+            .or_else(|| self.buffer_iter.next())
+            .or_else(|| self.back.take())?;
+        let mut length = 1;
+        loop {
+            match self.buffer_iter.next() {
+                Some(next) if next == index => length += 1,
+                Some(next) => {
+                    self.front = Some(next);
+                    break;
+                }
+                None => {
+                    if self.back == Some(index) {
+                        self.back = None;
+                        length += 1;
+                    }
+                    break;
+                }
+            }
+        }
+        Some(self.run(index, length))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.remaining();
+        if self.palette.index_size() == 0 {
+            let runs = remaining.min(1);
+            return (runs, Some(runs));
+        }
+        (remaining.min(1), Some(remaining))
+    }
+}
+
+impl<'a, T, P, B> DoubleEndedIterator for PaletteVecRuns<'a, T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T> + 'a,
+    B: IndexBuffer + 'a,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.palette.index_size() == 0 {
+            return self.take_uniform_run();
+        }
+        let index = self
+            .back
+            .take()
+            .or_else(|| self.buffer_iter.next_back())
+            .or_else(|| self.front.take())?;
+        let mut length = 1;
+        loop {
+            match self.buffer_iter.next_back() {
+                Some(next) if next == index => length += 1,
+                Some(next) => {
+                    self.back = Some(next);
+                    break;
+                }
+                None => {
+                    if self.front == Some(index) {
+                        self.front = None;
+                        length += 1;
+                    }
+                    break;
+                }
+            }
+        }
+        Some(self.run(index, length))
+    }
+}
+
+impl<'a, T, P, B> FusedIterator for PaletteVecRuns<'a, T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T> + 'a,
+    B: IndexBuffer + 'a,
+{
+}
+
+// HISTOGRAM
+pub struct PaletteVecHistogram<'a, T, P>
+where
+    T: Eq + Hash + Clone + 'a,
+    P: Palette<T> + 'a,
+{
+    entries: P::EntriesIter<'a>,
+}
+
+impl<'a, T, P> Iterator for PaletteVecHistogram<'a, T, P>
+where
+    T: Eq + Hash + Clone + 'a,
+    P: Palette<T> + 'a,
+{
+    type Item = (&'a T, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.entries.next()?;
+        Some((&entry.value, count_to_usize(entry.count)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<'a, T, P, B> IntoIterator for &'a PaletteVec<T, P, B>
 where
     T: Eq + Hash + Clone + 'a,
     P: Palette<T> + 'a,
@@ -347,4 +1606,171 @@ where
             phantom: PhantomData,
         }
     }
-}
\ No newline at end of file
+}
+
+impl<T, P, B> IntoIterator for PaletteVec<T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T>,
+    B: IndexBuffer,
+{
+    type Item = T;
+    type IntoIter = PaletteVecIntoIter<T, P, B>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        PaletteVecIntoIter {
+            end: self.len(),
+            offset: 0,
+            vec: self,
+        }
+    }
+}
+
+// OWNING ITERATOR
+/// Consumes a `PaletteVec`. Values are cloned out of the palette, except for the
+/// last occurrence of each value, which is moved out.
+#[derive(Debug, Clone)]
+pub struct PaletteVecIntoIter<T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T>,
+    B: IndexBuffer,
+{
+    vec: PaletteVec<T, P, B>,
+    offset: usize,
+    end: usize,
+}
+
+impl<T, P, B> Iterator for PaletteVecIntoIter<T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T>,
+    B: IndexBuffer,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.end {
+            return None;
+        }
+        let index = self.vec.buffer.get_index(self.offset);
+        self.offset += 1;
+        Some(self.vec.take_value(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.offset;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T, P, B> DoubleEndedIterator for PaletteVecIntoIter<T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T>,
+    B: IndexBuffer,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.end {
+            return None;
+        }
+        self.end -= 1;
+        let index = self.vec.buffer.get_index(self.end);
+        Some(self.vec.take_value(index))
+    }
+}
+
+impl<T, P, B> ExactSizeIterator for PaletteVecIntoIter<T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T>,
+    B: IndexBuffer,
+{
+}
+
+impl<T, P, B> FusedIterator for PaletteVecIntoIter<T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T>,
+    B: IndexBuffer,
+{
+}
+
+// DRAIN
+/// Iterator returned by [`PaletteVec::drain`].
+#[derive(Debug)]
+pub struct PaletteVecDrain<'a, T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T>,
+    B: IndexBuffer,
+{
+    indices: std::vec::IntoIter<usize>,
+    /// The drained values by palette index, with the amount of them still to be yielded
+    values: FxHashMap<usize, (T, CountType)>,
+    phantom: PhantomData<&'a mut PaletteVec<T, P, B>>,
+}
+
+impl<T, P, B> PaletteVecDrain<'_, T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T>,
+    B: IndexBuffer,
+{
+    /// Clones the value of `index`, or moves it out on its last occurrence.
+    fn yield_value(&mut self, index: usize) -> T {
+        let (value, remaining) = self.values.get_mut(&index).unwrap();
+        *remaining -= 1;
+        if *remaining == 0 {
+            self.values.remove(&index).unwrap().0
+        } else {
+            value.clone()
+        }
+    }
+}
+
+impl<T, P, B> Iterator for PaletteVecDrain<'_, T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T>,
+    B: IndexBuffer,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.indices.next()?;
+        Some(self.yield_value(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.indices.size_hint()
+    }
+}
+
+impl<T, P, B> DoubleEndedIterator for PaletteVecDrain<'_, T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T>,
+    B: IndexBuffer,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let index = self.indices.next_back()?;
+        Some(self.yield_value(index))
+    }
+}
+
+impl<T, P, B> ExactSizeIterator for PaletteVecDrain<'_, T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T>,
+    B: IndexBuffer,
+{
+}
+
+impl<T, P, B> FusedIterator for PaletteVecDrain<'_, T, P, B>
+where
+    T: Eq + Hash + Clone,
+    P: Palette<T>,
+    B: IndexBuffer,
+{
+}
@@ -0,0 +1,162 @@
+//! Import and export of the long array format used by Minecraft Java Edition (1.16+)
+//! for the `PalettedContainer` of chunk sections.
+//!
+//! The format stores `64 / bits` indices per long, starting at the least significant
+//! bits, and indices never cross a long boundary. That is the same layout the index
+//! buffers of this crate use, so converting mostly comes down to compacting the palette.
+use std::{fmt, hash::Hash};
+
+use crate::{
+    index_buffer::{packed, IndexBuffer},
+    palette::{calculate_smallest_index_size, Palette},
+    PaletteVec,
+};
+
+/// The palette and packed indices of a `PalettedContainer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedLongs<T> {
+    /// Bits per entry. 0 is the single value form, where `palette` holds exactly one
+    /// value (or none for an empty `PaletteVec`) and `longs` is empty.
+    pub bits: usize,
+    /// Dense list of values, the indices in `longs` point into it.
+    pub palette: Vec<T>,
+    pub longs: Vec<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackedLongsError {
+    /// More than 64 bits per entry.
+    BitsOutOfRange(usize),
+    /// The amount of longs doesn't match `len` and `bits`.
+    WrongLongCount { expected: usize, found: usize },
+    /// The single value form requires exactly one palette value.
+    SingleValuePalette(usize),
+    /// An index points past the end of the palette.
+    IndexOutOfPalette { offset: usize, index: usize },
+}
+
+impl fmt::Display for PackedLongsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackedLongsError::BitsOutOfRange(bits) => {
+                write!(f, "{} bits per entry are out of range (0..=64)", bits)
+            }
+            PackedLongsError::WrongLongCount { expected, found } => {
+                write!(f, "expected {} longs but found {}", expected, found)
+            }
+            PackedLongsError::SingleValuePalette(len) => write!(
+                f,
+                "single value form requires exactly one palette value, found {}",
+                len
+            ),
+            PackedLongsError::IndexOutOfPalette { offset, index } => write!(
+                f,
+                "index {} at offset {} is out of bounds for the palette",
+                index, offset
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PackedLongsError {}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteVec<T, P, B> {
+    /// Exports the data in the `PalettedContainer` long array format.
+    ///
+    /// With a single unique value, the single value form (`bits == 0`) is used.
+    /// Otherwise every entry takes at least `min_bits` bits, Minecraft uses 4 for
+    /// block states and 1 for biomes.
+    ///
+    /// The packed words are copied as they are if the palette has no holes and the
+    /// index buffer already uses the right amount of bits.
+    pub fn to_packed_longs(&self, min_bits: usize) -> PackedLongs<T> {
+        let entries = self.palette_entries_by_index();
+        let palette = entries
+            .iter()
+            .map(|(_, entry)| entry.value.clone())
+            .collect::<Vec<_>>();
+        if palette.len() <= 1 {
+            return PackedLongs {
+                bits: 0,
+                palette,
+                longs: Vec::new(),
+            };
+        }
+        let bits = calculate_smallest_index_size(palette.len()).max(min_bits).min(64);
+
+        let dense = entries.iter().enumerate().all(|(i, (index, _))| i == *index);
+        if dense && self.buffer.bits_per_index() == bits {
+            let indices_per_u64 = 64 / bits;
+            let mut longs = self.buffer.packed_words()[..self.len().div_ceil(indices_per_u64)].to_vec();
+            packed::clear_unused(&mut longs, bits, self.len());
+            return PackedLongs {
+                bits,
+                palette,
+                longs,
+            };
+        }
+
+        let mut mapping = vec![0; self.palette.index_bound()];
+        for (new_index, (index, _)) in entries.iter().enumerate() {
+            mapping[*index] = new_index;
+        }
+        let mut indices = Vec::with_capacity(self.len());
+        Self::for_each_index(&self.buffer, |index| indices.push(mapping[index]));
+        let mut longs = Vec::new();
+        packed::extend(&mut longs, bits, 0, &indices);
+        PackedLongs {
+            bits,
+            palette,
+            longs,
+        }
+    }
+
+    /// Imports `len` elements from the `PalettedContainer` long array format.
+    ///
+    /// Palette values that are never referenced are skipped and duplicate
+    /// palette values are merged. Malformed input returns an error.
+    pub fn from_packed_longs(
+        bits: usize,
+        len: usize,
+        palette: &[T],
+        longs: &[u64],
+    ) -> Result<Self, PackedLongsError> {
+        if bits > 64 {
+            return Err(PackedLongsError::BitsOutOfRange(bits));
+        }
+        if bits == 0 {
+            if !longs.is_empty() {
+                return Err(PackedLongsError::WrongLongCount {
+                    expected: 0,
+                    found: longs.len(),
+                });
+            }
+            return match palette {
+                [] if len == 0 => Ok(Self::new()),
+                [value] => Ok(Self::filled(value.clone(), len)),
+                _ => Err(PackedLongsError::SingleValuePalette(palette.len())),
+            };
+        }
+        let expected = len.div_ceil(64 / bits);
+        if longs.len() != expected {
+            return Err(PackedLongsError::WrongLongCount {
+                expected,
+                found: longs.len(),
+            });
+        }
+
+        let mut indices = Vec::with_capacity(len);
+        packed::for_each(longs, bits, len, |index| indices.push(index));
+        if let Some((offset, index)) = indices
+            .iter()
+            .enumerate()
+            .find(|(_, index)| **index >= palette.len())
+        {
+            return Err(PackedLongsError::IndexOutOfPalette {
+                offset,
+                index: *index,
+            });
+        }
+        Ok(Self::from_dense_parts(palette, indices))
+    }
+}
@@ -0,0 +1,163 @@
+//! The native binary format of this crate.
+//!
+//! All numbers are stored little-endian, regardless of the host:
+//!
+//! | Bytes     | Content                                                    |
+//! |-----------|------------------------------------------------------------|
+//! | 4         | Magic number `PVEC`                                        |
+//! | 2         | Format version                                             |
+//! | 8         | Element count                                              |
+//! | 1         | Bits per index, 0 if there is at most one unique value     |
+//! | 8         | Palette length                                             |
+//! | ...       | Palette values, written by a [`ValueCodec`]                |
+//! | 8 * words | Packed indices into the palette, `64 / bits` per word      |
+//!
+//! Indices start at the least significant bits of a word and never cross a
+//! word boundary. The layout doesn't depend on the `IndexBuffer`, so files stay
+//! readable when the in-memory layout changes.
+use std::{
+    hash::Hash,
+    io::{self, Read, Write},
+};
+
+use crate::{
+    index_buffer::IndexBuffer,
+    palette::{Palette, MAX_COUNT},
+    PaletteVec,
+};
+
+pub const MAGIC: [u8; 4] = *b"PVEC";
+pub const VERSION: u16 = 1;
+
+const HEADER_SIZE: usize = 4 + 2 + 8 + 1 + 8;
+
+/// Writes and reads the palette values of the native format.
+pub trait ValueCodec<T> {
+    fn encode<W: Write>(&mut self, value: &T, writer: &mut W) -> io::Result<()>;
+    fn decode<R: Read>(&mut self, reader: &mut R) -> io::Result<T>;
+}
+
+impl<T, C: ValueCodec<T>> ValueCodec<T> for &mut C {
+    fn encode<W: Write>(&mut self, value: &T, writer: &mut W) -> io::Result<()> {
+        (**self).encode(value, writer)
+    }
+
+    fn decode<R: Read>(&mut self, reader: &mut R) -> io::Result<T> {
+        (**self).decode(reader)
+    }
+}
+
+/// Stores primitive integers as their little-endian bytes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LeBytes;
+
+macro_rules! impl_le_bytes {
+    ($($ty:ty),*) => {
+        $(
+            impl ValueCodec<$ty> for LeBytes {
+                fn encode<W: Write>(&mut self, value: &$ty, writer: &mut W) -> io::Result<()> {
+                    writer.write_all(&value.to_le_bytes())
+                }
+
+                fn decode<R: Read>(&mut self, reader: &mut R) -> io::Result<$ty> {
+                    let mut bytes = [0; std::mem::size_of::<$ty>()];
+                    reader.read_exact(&mut bytes)?;
+                    Ok(<$ty>::from_le_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+impl_le_bytes!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteVec<T, P, B> {
+    /// Writes the elements in the native binary format, see the [`native`](crate::native)
+    /// module. Every unique value is encoded once by `codec`.
+    pub fn write_to<W: Write, C: ValueCodec<T>>(
+        &self,
+        mut writer: W,
+        mut codec: C,
+    ) -> io::Result<()> {
+        let packed = self.to_packed_longs(0);
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        header.extend_from_slice(&MAGIC);
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.extend_from_slice(&(self.len() as u64).to_le_bytes());
+        header.push(packed.bits as u8);
+        header.extend_from_slice(&(packed.palette.len() as u64).to_le_bytes());
+        writer.write_all(&header)?;
+        for value in packed.palette.iter() {
+            codec.encode(value, &mut writer)?;
+        }
+        let words = packed
+            .longs
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect::<Vec<_>>();
+        writer.write_all(&words)
+    }
+
+    /// Reads elements in the native binary format, decoding the palette values with `codec`.
+    ///
+    /// Malformed data fails with `InvalidData`.
+    pub fn read_from<R: Read, C: ValueCodec<T>>(
+        mut reader: R,
+        mut codec: C,
+    ) -> io::Result<Self> {
+        let mut header = [0u8; HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+        if header[..4] != MAGIC {
+            return Err(invalid_data("Not a PaletteVec file"));
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != VERSION {
+            return Err(invalid_data(format!(
+                "Unsupported PaletteVec format version {}",
+                version
+            )));
+        }
+        let len = u64::from_le_bytes(header[6..14].try_into().unwrap());
+        // MAX_COUNT is usize::MAX with count-usize
+        #[allow(clippy::absurd_extreme_comparisons)]
+        let len = usize::try_from(len)
+            .ok()
+            .filter(|len| *len <= MAX_COUNT)
+            .ok_or_else(|| invalid_data("Too many elements"))?;
+        let bits = header[14] as usize;
+        if bits > 64 {
+            return Err(invalid_data(format!("{} bits per index are out of range", bits)));
+        }
+        let palette_len = u64::from_le_bytes(header[15..23].try_into().unwrap());
+        // Every palette value has to be addressable
+        if bits < 64 && palette_len > 1 << bits {
+            return Err(invalid_data("Palette too large for the bits per index"));
+        }
+
+        // Don't trust the length for the allocation
+        let mut palette = Vec::with_capacity((palette_len as usize).min(1024));
+        for _ in 0..palette_len {
+            palette.push(codec.decode(&mut reader)?);
+        }
+
+        let words = 64usize.checked_div(bits).map_or(0, |per_word| len.div_ceil(per_word));
+        let word_bytes = words
+            .checked_mul(8)
+            .ok_or_else(|| invalid_data("Too many elements"))?;
+        let mut bytes = Vec::new();
+        (&mut reader).take(word_bytes as u64).read_to_end(&mut bytes)?;
+        if bytes.len() != word_bytes {
+            return Err(invalid_data("PaletteVec data is truncated"));
+        }
+        let words = bytes
+            .chunks_exact(8)
+            .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        Self::from_packed_longs(bits, len, &palette, &words)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
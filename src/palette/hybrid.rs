@@ -7,11 +7,11 @@
 
 use std::{collections::hash_map, hash::Hash, iter::FilterMap};
 
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{
-    palette::{calculate_smallest_index_size, compare_palette_entries_max_first},
-    MemoryUsage,
+    palette::{calculate_smallest_index_size, check_entries, compare_palette_entries_max_first},
+    InvariantError, MemoryUsage,
 };
 
 use super::{compare_palette_entries_option_max_first, Palette, PaletteEntry};
@@ -23,7 +23,7 @@ use super::{compare_palette_entries_option_max_first, Palette, PaletteEntry};
 /// if this threshold is exceeded. This provides a balance between performance
 /// for small palettes and scalability for larger ones.
 #[derive(Clone)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(feature = "bitcode", derive(bitcode::Encode, bitcode::Decode))]
 pub struct HybridPalette<const INLINE_PALETTE_THRESHOLD: usize, T: Eq + Hash + Clone> {
     index_size: usize,
@@ -31,6 +31,31 @@ pub struct HybridPalette<const INLINE_PALETTE_THRESHOLD: usize, T: Eq + Hash + C
     storage: HybridStorage<INLINE_PALETTE_THRESHOLD, T>,
 }
 
+#[cfg(feature = "serde")]
+impl<'de, const INLINE_PALETTE_THRESHOLD: usize, T> serde::Deserialize<'de>
+    for HybridPalette<INLINE_PALETTE_THRESHOLD, T>
+where
+    T: Eq + Hash + Clone + serde::Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "HybridPalette")]
+        struct Unchecked<const INLINE_PALETTE_THRESHOLD: usize, T: Eq + Hash + Clone> {
+            index_size: usize,
+            real_entries: usize,
+            storage: HybridStorage<INLINE_PALETTE_THRESHOLD, T>,
+        }
+        let unchecked = Unchecked::deserialize(deserializer)?;
+        let palette = Self {
+            index_size: unchecked.index_size,
+            real_entries: unchecked.real_entries,
+            storage: unchecked.storage,
+        };
+        palette.check_invariants().map_err(serde::de::Error::custom)?;
+        Ok(palette)
+    }
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "bitcode", derive(bitcode::Encode, bitcode::Decode))]
@@ -66,8 +91,6 @@ impl<const INLINE_PALETTE_THRESHOLD: usize, T: Eq + Hash + Clone>
                     }
                 }
                 debug_assert_eq!(index_map.len(), value_map.len());
-                // Make sure the array is full before switching to hashmap
-                debug_assert_eq!(index_map.len(), INLINE_PALETTE_THRESHOLD);
                 self.storage = HybridStorage::HashMap {
                     free_indices,
                     index_map,
@@ -214,7 +237,45 @@ impl<const INLINE_PALETTE_THRESHOLD: usize, T: Eq + Hash + Clone> Palette<T>
         }
     }
 
-    /*fn get_by_value(&self, value: &T) -> Option<(&PaletteEntry<T>, usize)> {
+    fn take_unused(&mut self, index: usize) -> T {
+        self.real_entries -= 1;
+        match &mut self.storage {
+            HybridStorage::Array { array, .. } => array[index].take().unwrap().value,
+            HybridStorage::HashMap {
+                free_indices,
+                index_map,
+                value_map,
+            } => {
+                free_indices.push(index);
+                let entry = index_map.remove(&index).unwrap();
+                debug_assert_eq!(entry.count, 0);
+                value_map.remove(&entry.value);
+                entry.value
+            }
+        }
+    }
+
+    fn replace_value(&mut self, index: usize, value: T) -> T {
+        match &mut self.storage {
+            HybridStorage::Array { array } => {
+                let entry = array[index].as_mut().unwrap();
+                std::mem::replace(&mut entry.value, value)
+            }
+            HybridStorage::HashMap {
+                index_map,
+                value_map,
+                ..
+            } => {
+                debug_assert!(!value_map.contains_key(&value));
+                let entry = index_map.get_mut(&index).unwrap();
+                value_map.remove(&entry.value);
+                value_map.insert(value.clone(), index);
+                std::mem::replace(&mut entry.value, value)
+            }
+        }
+    }
+
+    fn get_by_value(&self, value: &T) -> Option<(&PaletteEntry<T>, usize)> {
         match &self.storage {
             HybridStorage::Array { array, .. } => {
                 for (index, entry) in array.iter().enumerate() {
@@ -235,7 +296,7 @@ impl<const INLINE_PALETTE_THRESHOLD: usize, T: Eq + Hash + Clone> Palette<T>
                 index_map.get(index).map(|entry| (entry, *index))
             }
         }
-    }*/
+    }
 
     fn get_mut_by_value(&mut self, value: &T) -> Option<(&mut PaletteEntry<T>, usize)> {
         match &mut self.storage {
@@ -274,6 +335,18 @@ impl<const INLINE_PALETTE_THRESHOLD: usize, T: Eq + Hash + Clone> Palette<T>
         }
     }
 
+    fn index_bound(&self) -> usize {
+        match &self.storage {
+            HybridStorage::Array { .. } => INLINE_PALETTE_THRESHOLD,
+            // Every index below the bound is either in use or free
+            HybridStorage::HashMap {
+                free_indices,
+                index_map,
+                ..
+            } => index_map.len() + free_indices.len(),
+        }
+    }
+
     fn insert_new(&mut self, entry: PaletteEntry<T>) -> (usize, Option<usize>) {
         debug_assert!(entry.count > 0);
         match &mut self.storage {
@@ -292,7 +365,9 @@ impl<const INLINE_PALETTE_THRESHOLD: usize, T: Eq + Hash + Clone> Palette<T>
                         return (i, actual_new_index_size);
                     }
                 }
-                // No free spot available, need to switch to hashmap
+                // No free spot available, need to switch to hashmap.
+                // Only insert_at switches with holes in the array.
+                debug_assert_eq!(array.iter().flatten().count(), INLINE_PALETTE_THRESHOLD);
                 self.switch_to_hashmap();
                 self.insert_new(entry)
             }
@@ -325,6 +400,48 @@ impl<const INLINE_PALETTE_THRESHOLD: usize, T: Eq + Hash + Clone> Palette<T>
         }
     }
 
+    fn insert_at(&mut self, index: usize, entry: PaletteEntry<T>) -> Option<usize> {
+        debug_assert!(entry.count > 0);
+        let bound = self.index_bound();
+        match &mut self.storage {
+            HybridStorage::Array { array } if index < INLINE_PALETTE_THRESHOLD => {
+                debug_assert!(array[index].as_ref().is_none_or(|old| old.count == 0));
+                array[index] = Some(entry);
+            }
+            HybridStorage::Array { .. } => {
+                // Holes of the array become free indices
+                self.switch_to_hashmap();
+                return self.insert_at(index, entry);
+            }
+            HybridStorage::HashMap {
+                free_indices,
+                index_map,
+                value_map,
+            } => {
+                if index < bound {
+                    let position = free_indices.iter().position(|free| *free == index);
+                    debug_assert!(position.is_some());
+                    if let Some(position) = position {
+                        free_indices.swap_remove(position);
+                    }
+                } else {
+                    free_indices.extend(bound..index);
+                }
+                value_map.insert(entry.value.clone(), index);
+                index_map.insert(index, entry);
+            }
+        }
+        self.real_entries += 1;
+        // The index itself has to fit, even if there are holes below it
+        let new_index_size = calculate_smallest_index_size(self.real_entries)
+            .max(calculate_smallest_index_size(index + 1));
+        if new_index_size > self.index_size {
+            self.index_size = new_index_size;
+            return Some(new_index_size);
+        }
+        None
+    }
+
     fn optimize(&mut self) -> Option<FxHashMap<usize, usize>> {
         self.index_size = calculate_smallest_index_size(self.real_entries);
         match &mut self.storage {
@@ -406,6 +523,62 @@ impl<const INLINE_PALETTE_THRESHOLD: usize, T: Eq + Hash + Clone> Palette<T>
         }
     }
 
+    fn check_invariants(&self) -> Result<(), InvariantError> {
+        match &self.storage {
+            HybridStorage::Array { array } => check_entries(
+                array
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, entry)| Some((index, entry.as_ref()?))),
+                self.index_size,
+                self.real_entries,
+            ),
+            HybridStorage::HashMap {
+                free_indices,
+                index_map,
+                value_map,
+            } => {
+                // Used and free indices have to cover every index below the bound exactly once
+                let bound = self.index_bound();
+                if index_map.keys().any(|index| *index >= bound) {
+                    return Err(InvariantError::InconsistentField("index_map"));
+                }
+                let mut free = FxHashSet::default();
+                for index in free_indices.iter() {
+                    if *index >= bound || index_map.contains_key(index) || !free.insert(*index) {
+                        return Err(InvariantError::InconsistentField("free_indices"));
+                    }
+                }
+                // insert_new hands out free indices without growing the index size,
+                // so they have to fit as well
+                let fits = |index: usize| self.index_size >= 64 || index >> self.index_size == 0;
+                if let Some(index) = free_indices
+                    .iter()
+                    .copied()
+                    .chain(bound.checked_sub(1))
+                    .find(|index| !fits(*index))
+                {
+                    return Err(InvariantError::IndexTooLarge {
+                        index,
+                        index_size: self.index_size,
+                    });
+                }
+                if value_map.len() != index_map.len()
+                    || index_map
+                        .iter()
+                        .any(|(index, entry)| value_map.get(&entry.value) != Some(index))
+                {
+                    return Err(InvariantError::InconsistentField("value_map"));
+                }
+                check_entries(
+                    index_map.iter().map(|(index, entry)| (*index, entry)),
+                    self.index_size,
+                    self.real_entries,
+                )
+            }
+        }
+    }
+
     type EntriesIter<'a>
         = HybridPaletteEntriesIter<'a, T>
     where
@@ -2,11 +2,11 @@
 //!
 //! HybridPalette is a good default.
 
-use std::cmp::Ordering;
+use std::{cmp::Ordering, hash::Hash};
 
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 
-use crate::MemoryUsage;
+use crate::{InvariantError, MemoryUsage};
 
 pub mod hybrid;
 pub mod vec;
@@ -44,6 +44,16 @@ pub type CountType = u16;
 ))]
 pub type CountType = u32;
 
+/// The highest count a palette entry can hold.
+#[allow(clippy::unnecessary_cast)]
+pub(crate) const MAX_COUNT: usize = CountType::MAX as usize;
+
+/// Widens a count. A plain cast trips clippy when `CountType` is `usize`.
+#[allow(clippy::unnecessary_cast)]
+pub(crate) fn count_to_usize(count: CountType) -> usize {
+    count as usize
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "bitcode", derive(bitcode::Encode, bitcode::Decode))]
@@ -73,6 +83,37 @@ fn compare_palette_entries_max_first<T: Eq + Clone>(
     b.count.cmp(&a.count)
 }
 
+/// Checks the entries of a palette together with their indices: counts must be
+/// above 0, values unique, and every index has to fit into index_size.
+fn check_entries<'a, T: Eq + Hash + Clone + 'a>(
+    entries: impl Iterator<Item = (usize, &'a PaletteEntry<T>)>,
+    index_size: usize,
+    real_entries: usize,
+) -> Result<(), InvariantError> {
+    if index_size > 64 {
+        return Err(InvariantError::UnsupportedIndexSize(index_size));
+    }
+    let mut values = FxHashSet::default();
+    for (index, entry) in entries {
+        if entry.count == 0 {
+            return Err(InvariantError::ZeroCount { index });
+        }
+        if index_size < 64 && index >> index_size != 0 {
+            return Err(InvariantError::IndexTooLarge { index, index_size });
+        }
+        if !values.insert(&entry.value) {
+            return Err(InvariantError::DuplicateValue { index });
+        }
+    }
+    if values.len() != real_entries {
+        return Err(InvariantError::EntryCount {
+            expected: real_entries,
+            found: values.len(),
+        });
+    }
+    Ok(())
+}
+
 pub(crate) fn calculate_smallest_index_size(n: usize) -> usize {
     if n == 0 {
         return 0;
@@ -93,22 +134,42 @@ pub trait Palette<T: Eq + Clone>: Clone {
     /// Clears the palette, dropping all entries.
     fn clear(&mut self);
 
-    //fn get_by_value(&self, value: &T) -> Option<(&PaletteEntry<T>, usize)>;
+    fn get_by_value(&self, value: &T) -> Option<(&PaletteEntry<T>, usize)>;
     fn get_mut_by_value(&mut self, value: &T) -> Option<(&mut PaletteEntry<T>, usize)>;
     fn get_by_index(&self, index: usize) -> Option<&PaletteEntry<T>>;
     fn get_mut_by_index(&mut self, index: usize) -> Option<&mut PaletteEntry<T>>;
+    /// Returns an exclusive upper bound for all indices that are in use.
+    /// Every index below it can be passed to get_by_index().
+    fn index_bound(&self) -> usize;
 
     /// IMPORTANT: Call this immediately after setting a palette entries count to 0.
     fn mark_as_unused(&mut self, index: usize);
 
+    /// Same as mark_as_unused(), but moves the value out of the palette
+    /// instead of dropping it.
+    fn take_unused(&mut self, index: usize) -> T;
+
+    /// Replaces the value of the entry at index in place, keeping its count and index.
+    /// Assumes that the palette doesn't contain the new value yet.
+    /// Returns the old value.
+    fn replace_value(&mut self, index: usize, value: T) -> T;
+
     /// Assumes that the palette doesn't contain this value yet.
     /// Returns the new index and the new index size if needed.
     /// This function is not allowed to change any of the other indices.
     fn insert_new(&mut self, entry: PaletteEntry<T>) -> (usize, Option<usize>);
+    /// Same as insert_new(), but places the entry at the given index, which must be unused.
+    /// Indices between the old index_bound() and index become free.
+    /// Returns the new index size if needed.
+    fn insert_at(&mut self, index: usize, entry: PaletteEntry<T>) -> Option<usize>;
     /// Optimizes the palette and returns the mapping of old_index -> new_index
     /// if necessary.
     fn optimize(&mut self) -> Option<FxHashMap<usize, usize>>;
 
+    /// Checks the internal structure, for example after deserializing untrusted data.
+    /// Must not panic, no matter the field values.
+    fn check_invariants(&self) -> Result<(), InvariantError>;
+
     // REF ITERATOR
     type EntriesIter<'a>: Iterator<Item = &'a PaletteEntry<T>>
     where
@@ -5,9 +5,10 @@ use rustc_hash::FxHashMap;
 
 use crate::{
     palette::{
-        calculate_smallest_index_size, compare_palette_entries_option_max_first, PaletteEntry,
+        calculate_smallest_index_size, check_entries, compare_palette_entries_option_max_first,
+        PaletteEntry,
     },
-    MemoryUsage,
+    InvariantError, MemoryUsage,
 };
 
 /// A Palette based purely on a heap allocated vec.
@@ -16,7 +17,7 @@ use crate::{
 ///
 /// Also very memory efficient and no danger of stack overflow.
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(feature = "bitcode", derive(bitcode::Encode, bitcode::Decode))]
 pub struct VecPalette<T: Eq + Hash + Clone> {
     index_size: usize,
@@ -24,6 +25,29 @@ pub struct VecPalette<T: Eq + Hash + Clone> {
     storage: Vec<Option<PaletteEntry<T>>>,
 }
 
+#[cfg(feature = "serde")]
+impl<'de, T: Eq + Hash + Clone + serde::Deserialize<'de>> serde::Deserialize<'de>
+    for VecPalette<T>
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "VecPalette")]
+        struct Unchecked<T: Eq + Clone> {
+            index_size: usize,
+            real_entries: usize,
+            storage: Vec<Option<PaletteEntry<T>>>,
+        }
+        let unchecked = Unchecked::deserialize(deserializer)?;
+        let palette = Self {
+            index_size: unchecked.index_size,
+            real_entries: unchecked.real_entries,
+            storage: unchecked.storage,
+        };
+        palette.check_invariants().map_err(serde::de::Error::custom)?;
+        Ok(palette)
+    }
+}
+
 impl<T: Eq + Hash + Clone> Palette<T> for VecPalette<T> {
     fn new() -> Self {
         VecPalette {
@@ -65,6 +89,28 @@ impl<T: Eq + Hash + Clone> Palette<T> for VecPalette<T> {
         self.storage[index] = None;
     }
 
+    fn take_unused(&mut self, index: usize) -> T {
+        debug_assert!(self.storage[index].is_some());
+        self.real_entries -= 1;
+        self.storage[index].take().unwrap().value
+    }
+
+    fn replace_value(&mut self, index: usize, value: T) -> T {
+        let entry = self.storage[index].as_mut().unwrap();
+        std::mem::replace(&mut entry.value, value)
+    }
+
+    fn get_by_value(&self, value: &T) -> Option<(&PaletteEntry<T>, usize)> {
+        for (index, entry) in self.storage.iter().enumerate() {
+            if let Some(entry) = entry {
+                if &entry.value == value {
+                    return Some((entry, index));
+                }
+            }
+        }
+        None
+    }
+
     fn get_mut_by_value(&mut self, value: &T) -> Option<(&mut PaletteEntry<T>, usize)> {
         for (index, entry) in self.storage.iter_mut().enumerate() {
             if let Some(entry) = entry {
@@ -84,6 +130,10 @@ impl<T: Eq + Hash + Clone> Palette<T> for VecPalette<T> {
         self.storage[index].as_mut()
     }
 
+    fn index_bound(&self) -> usize {
+        self.storage.len()
+    }
+
     fn insert_new(&mut self, entry: PaletteEntry<T>) -> (usize, Option<usize>) {
         debug_assert!(entry.count > 0);
         // Try to use free spot
@@ -114,6 +164,24 @@ impl<T: Eq + Hash + Clone> Palette<T> for VecPalette<T> {
         (index, actual_new_index_size)
     }
 
+    fn insert_at(&mut self, index: usize, entry: PaletteEntry<T>) -> Option<usize> {
+        debug_assert!(entry.count > 0);
+        if index >= self.storage.len() {
+            self.storage.resize(index + 1, None);
+        }
+        debug_assert!(self.storage[index].as_ref().is_none_or(|old| old.count == 0));
+        self.storage[index] = Some(entry);
+        self.real_entries += 1;
+        // The index itself has to fit, even if there are holes below it
+        let new_index_size = calculate_smallest_index_size(self.real_entries)
+            .max(calculate_smallest_index_size(index + 1));
+        if new_index_size > self.index_size {
+            self.index_size = new_index_size;
+            return Some(new_index_size);
+        }
+        None
+    }
+
     fn optimize(&mut self) -> Option<FxHashMap<usize, usize>> {
         self.index_size = calculate_smallest_index_size(self.real_entries);
         // To optimize the vec palette, we sort palette
@@ -152,6 +220,17 @@ impl<T: Eq + Hash + Clone> Palette<T> for VecPalette<T> {
         None
     }
 
+    fn check_invariants(&self) -> Result<(), InvariantError> {
+        check_entries(
+            self.storage
+                .iter()
+                .enumerate()
+                .filter_map(|(index, entry)| Some((index, entry.as_ref()?))),
+            self.index_size,
+            self.real_entries,
+        )
+    }
+
     type EntriesIter<'a>
         = VecPaletteEntriesIter<'a, T>
     where
@@ -0,0 +1,136 @@
+//! Import and export of block data as stored by Sponge schematics: a palette
+//! mapping keys to ids, plus a byte stream of LEB128 varint encoded ids.
+use std::{fmt, hash::Hash};
+
+use rustc_hash::FxHashMap;
+
+use crate::{index_buffer::IndexBuffer, palette::Palette, PaletteVec};
+
+/// Block data in the varint format. Every element is stored as the varint
+/// encoded id of its palette key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VarintData<K> {
+    /// Keys and their ids.
+    pub palette: Vec<(K, u32)>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VarintDataError {
+    /// The stream ends in the middle of a varint.
+    Truncated { offset: usize },
+    /// A varint doesn't fit into a u32.
+    Overflow { offset: usize },
+    /// An id in the stream doesn't exist in the palette.
+    UnknownId { offset: usize, id: u32 },
+    /// The palette contains the same id twice.
+    DuplicateId(u32),
+    /// The stream doesn't contain the expected amount of elements.
+    WrongLength { expected: usize, found: usize },
+}
+
+impl fmt::Display for VarintDataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VarintDataError::Truncated { offset } => {
+                write!(f, "varint at byte {} is truncated", offset)
+            }
+            VarintDataError::Overflow { offset } => {
+                write!(f, "varint at byte {} doesn't fit into a u32", offset)
+            }
+            VarintDataError::UnknownId { offset, id } => {
+                write!(f, "id {} at byte {} doesn't exist in the palette", id, offset)
+            }
+            VarintDataError::DuplicateId(id) => write!(f, "id {} exists twice in the palette", id),
+            VarintDataError::WrongLength { expected, found } => {
+                write!(f, "expected {} elements but found {}", expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for VarintDataError {}
+
+fn write_varint(data: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        data.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    data.push(value as u8);
+}
+
+/// Reads the varint starting at `offset`. Returns the value and the offset behind it.
+fn read_varint(data: &[u8], offset: usize) -> Result<(u32, usize), VarintDataError> {
+    let mut value = 0u32;
+    for (i, byte) in data[offset..].iter().enumerate() {
+        if i == 4 && *byte > 0x0F {
+            // The fifth byte may only contain the 4 remaining bits
+            return Err(VarintDataError::Overflow { offset });
+        }
+        value |= ((byte & 0x7F) as u32) << (i * 7);
+        if byte & 0x80 == 0 {
+            return Ok((value, offset + i + 1));
+        }
+    }
+    Err(VarintDataError::Truncated { offset })
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteVec<T, P, B> {
+    /// Exports the elements as varint encoded ids. `key` is called once per
+    /// unique value to produce its palette key. Ids are assigned densely from 0.
+    pub fn to_varint_data<K>(&self, mut key: impl FnMut(&T) -> K) -> VarintData<K> {
+        let entries = self.palette_entries_by_index();
+        let mut ids = vec![0; self.palette.index_bound()];
+        let mut palette = Vec::with_capacity(entries.len());
+        for (id, (index, entry)) in entries.into_iter().enumerate() {
+            ids[index] = id as u32;
+            palette.push((key(&entry.value), id as u32));
+        }
+        let mut data = Vec::with_capacity(self.len());
+        Self::for_each_index(&self.buffer, |index| write_varint(&mut data, ids[index]));
+        VarintData { palette, data }
+    }
+
+    /// Imports `len` elements from varint encoded ids. `value` is called once per
+    /// palette key to produce the value it stands for. Keys producing the same value
+    /// are merged.
+    ///
+    /// Malformed data, like unknown ids or a wrong amount of elements, returns an error.
+    pub fn from_varint_data<K>(
+        palette: &[(K, u32)],
+        data: &[u8],
+        len: usize,
+        mut value: impl FnMut(&K) -> T,
+    ) -> Result<Self, VarintDataError> {
+        let mut slots = FxHashMap::default();
+        for (slot, (_, id)) in palette.iter().enumerate() {
+            if slots.insert(*id, slot).is_some() {
+                return Err(VarintDataError::DuplicateId(*id));
+            }
+        }
+
+        // Every element takes at least one byte
+        let mut indices = Vec::with_capacity(len.min(data.len()));
+        let mut offset = 0;
+        while offset < data.len() {
+            let (id, next) = read_varint(data, offset)?;
+            let Some(slot) = slots.get(&id) else {
+                return Err(VarintDataError::UnknownId { offset, id });
+            };
+            indices.push(*slot);
+            offset = next;
+        }
+        if indices.len() != len {
+            return Err(VarintDataError::WrongLength {
+                expected: len,
+                found: indices.len(),
+            });
+        }
+
+        let values = palette
+            .iter()
+            .map(|(key, _)| value(key))
+            .collect::<Vec<_>>();
+        Ok(Self::from_dense_parts(&values, indices))
+    }
+}
@@ -0,0 +1,215 @@
+//! Borrowed views into a sub-range of a `PaletteVec`.
+//!
+//! A `PaletteSlice` shares the palette and index buffer of the `PaletteVec`
+//! it was created from, so creating one never copies any data.
+use std::{
+    hash::Hash,
+    ops::{Index, Range, RangeBounds},
+};
+
+use crate::{
+    index_buffer::IndexBuffer,
+    palette::Palette,
+    resolve_range, PaletteVec, PaletteVecIter, PaletteVecRuns,
+};
+
+/// An immutable view into a range of a `PaletteVec`. Created by [`PaletteVec::slice`].
+#[derive(Debug)]
+pub struct PaletteSlice<'a, T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> {
+    vec: &'a PaletteVec<T, P, B>,
+    range: Range<usize>,
+}
+
+impl<'a, T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteSlice<'a, T, P, B> {
+    pub(crate) fn new(vec: &'a PaletteVec<T, P, B>, range: impl RangeBounds<usize>) -> Self {
+        let range = resolve_range(range, vec.len());
+        Self { vec, range }
+    }
+
+    pub fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+
+    /// Returns the element at `offset`, relative to the start of the slice.
+    pub fn get(&self, offset: usize) -> Option<&'a T> {
+        if offset >= self.len() {
+            return None;
+        }
+        self.vec.get(self.range.start + offset)
+    }
+
+    /// Returns a narrower view. The range is relative to the start of this slice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> Self {
+        let range = resolve_range(range, self.len());
+        Self {
+            vec: self.vec,
+            range: self.range.start + range.start..self.range.start + range.end,
+        }
+    }
+
+    pub fn iter(&self) -> PaletteVecIter<'a, T, P, B> {
+        self.vec.iter_range(self.range.clone())
+    }
+
+    /// Returns an iterator over runs of equal elements within the slice.
+    /// See [`PaletteVec::iter_runs`].
+    pub fn iter_runs(&self) -> PaletteVecRuns<'a, T, P, B> {
+        PaletteVecRuns::new(&self.vec.palette, self.vec.buffer.iter_range(self.range.clone()))
+    }
+
+    /// Returns how often `value` occurs within the slice.
+    pub fn count_of(&self, value: &T) -> usize {
+        let Some((_, index)) = self.vec.palette.get_by_value(value) else {
+            return 0;
+        };
+        if self.vec.palette.index_size() == 0 {
+            return self.len();
+        }
+        self.vec
+            .buffer
+            .iter_range(self.range.clone())
+            .filter(|other| *other == index)
+            .count()
+    }
+
+    /// Copies the slice into a new `PaletteVec` that only contains the
+    /// palette entries used within the slice.
+    pub fn to_palette_vec(&self) -> PaletteVec<T, P, B> {
+        self.vec.copy_range(self.range.clone())
+    }
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> Clone for PaletteSlice<'_, T, P, B> {
+    fn clone(&self) -> Self {
+        Self {
+            vec: self.vec,
+            range: self.range.clone(),
+        }
+    }
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> Index<usize> for PaletteSlice<'_, T, P, B> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).unwrap_or_else(|| {
+            panic!(
+                "Index {} out of bounds for PaletteSlice with length {}",
+                index,
+                self.len()
+            )
+        })
+    }
+}
+
+impl<'a, T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> IntoIterator
+    for &PaletteSlice<'a, T, P, B>
+{
+    type Item = &'a T;
+    type IntoIter = PaletteVecIter<'a, T, P, B>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A mutable view into a range of a `PaletteVec`. Created by [`PaletteVec::slice_mut`].
+///
+/// Writes only ever touch elements inside the range, but they update the palette
+/// of the underlying `PaletteVec` just like writing to it directly would.
+#[derive(Debug)]
+pub struct PaletteSliceMut<'a, T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> {
+    vec: &'a mut PaletteVec<T, P, B>,
+    range: Range<usize>,
+}
+
+impl<'a, T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> PaletteSliceMut<'a, T, P, B> {
+    pub(crate) fn new(vec: &'a mut PaletteVec<T, P, B>, range: impl RangeBounds<usize>) -> Self {
+        let range = resolve_range(range, vec.len());
+        Self { vec, range }
+    }
+
+    /// Reborrows this view as an immutable `PaletteSlice`.
+    pub fn as_slice(&self) -> PaletteSlice<'_, T, P, B> {
+        PaletteSlice {
+            vec: self.vec,
+            range: self.range.clone(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+
+    /// Returns the element at `offset`, relative to the start of the slice.
+    pub fn get(&self, offset: usize) -> Option<&T> {
+        if offset >= self.len() {
+            return None;
+        }
+        self.vec.get(self.range.start + offset)
+    }
+
+    /// Sets the element at `offset`, relative to the start of the slice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset >= len`.
+    pub fn set(&mut self, offset: usize, value: &T) {
+        assert!(
+            offset < self.len(),
+            "Index {} out of bounds for PaletteSliceMut with length {}",
+            offset,
+            self.len()
+        );
+        self.vec.set(self.range.start + offset, value);
+    }
+
+    /// Overwrites every element of the slice with `value`.
+    pub fn fill(&mut self, value: &T) {
+        self.vec.fill_range(self.range.clone(), value);
+    }
+
+    pub fn iter(&self) -> PaletteVecIter<'_, T, P, B> {
+        self.vec.iter_range(self.range.clone())
+    }
+
+    pub fn iter_runs(&self) -> PaletteVecRuns<'_, T, P, B> {
+        self.as_slice().iter_runs()
+    }
+
+    pub fn count_of(&self, value: &T) -> usize {
+        self.as_slice().count_of(value)
+    }
+
+    pub fn to_palette_vec(&self) -> PaletteVec<T, P, B> {
+        self.as_slice().to_palette_vec()
+    }
+}
+
+impl<T: Eq + Hash + Clone, P: Palette<T>, B: IndexBuffer> Index<usize>
+    for PaletteSliceMut<'_, T, P, B>
+{
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).unwrap_or_else(|| {
+            panic!(
+                "Index {} out of bounds for PaletteSliceMut with length {}",
+                index,
+                self.len()
+            )
+        })
+    }
+}
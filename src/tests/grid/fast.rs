@@ -0,0 +1,32 @@
+use crate::{index_buffer::fast::FastIndexBuffer, palette::vec::VecPalette};
+
+use super::*;
+
+#[test]
+fn grid3_rng_operations() {
+    let mut rng = ChaCha8Rng::seed_from_u64(4471029384);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        for layout in [GridLayout::Linear, GridLayout::Morton] {
+            test_grid3_rng_operations::<VecPalette<u32>, FastIndexBuffer>(seed, layout, 300);
+        }
+    }
+}
+
+#[test]
+fn grid2_rng_operations() {
+    let mut rng = ChaCha8Rng::seed_from_u64(8812398471);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_grid2_rng_operations::<VecPalette<u32>, FastIndexBuffer>(seed, 500);
+    }
+}
+
+#[test]
+fn grid2_bmp() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1029384756);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_grid2_bmp::<VecPalette<u32>, FastIndexBuffer>(seed);
+    }
+}
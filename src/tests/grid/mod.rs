@@ -0,0 +1,287 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    grid::{morton_decode, morton_encode, Axis, GridLayout, PaletteGrid2, PaletteGrid3},
+    index_buffer::IndexBuffer,
+    palette::Palette,
+};
+
+use super::calc_rng_iterations;
+
+mod base;
+mod fast;
+
+#[test]
+fn morton_roundtrip() {
+    for bits in [[0, 0, 0], [1, 1, 1], [4, 4, 4], [4, 8, 4], [0, 3, 5], [5, 2, 0]] {
+        let len = 1 << bits.iter().sum::<u32>();
+        let mut seen = vec![false; len];
+        for offset in 0..len {
+            let coords = morton_decode(offset, bits);
+            assert!(coords.iter().zip(bits).all(|(coord, bits)| *coord < 1 << bits));
+            let encoded = morton_encode(coords, bits);
+            assert_eq!(encoded, offset);
+            assert!(!seen[encoded]);
+            seen[encoded] = true;
+        }
+    }
+    // Lowest bits are interleaved x, y, z
+    assert_eq!(morton_encode([1, 0, 0], [4, 4, 4]), 1);
+    assert_eq!(morton_encode([0, 1, 0], [4, 4, 4]), 2);
+    assert_eq!(morton_encode([0, 0, 1], [4, 4, 4]), 4);
+    assert_eq!(morton_encode([2, 0, 0], [4, 4, 4]), 8);
+}
+
+fn random_dimensions(rng: &mut ChaCha8Rng, layout: GridLayout) -> [usize; 3] {
+    match layout {
+        GridLayout::Linear => [
+            rng.random_range(1..20),
+            rng.random_range(1..20),
+            rng.random_range(1..20),
+        ],
+        GridLayout::Morton => [
+            1 << rng.random_range(0..5),
+            1 << rng.random_range(0..5),
+            1 << rng.random_range(0..5),
+        ],
+    }
+}
+
+fn test_grid3_rng_operations<P, B>(seed: u64, layout: GridLayout, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let [width, height, depth] = random_dimensions(&mut rng, layout);
+    let control_offset = |x: usize, y: usize, z: usize| x + y * width + z * width * height;
+    let mut grid: PaletteGrid3<u32, P, B> = PaletteGrid3::new(width, height, depth, layout, 0);
+    let mut control = vec![0; width * height * depth];
+    assert_eq!(grid.len(), control.len());
+    assert_eq!(grid.dimensions(), [width, height, depth]);
+    assert_eq!(grid.uniform_value(), Some(&0));
+
+    for _ in 0..iteration_count {
+        match rng.random_range(0..10) {
+            0 => {
+                let min = [
+                    rng.random_range(0..=width),
+                    rng.random_range(0..=height),
+                    rng.random_range(0..=depth),
+                ];
+                let max = [
+                    rng.random_range(min[0]..=width),
+                    rng.random_range(min[1]..=height),
+                    rng.random_range(min[2]..=depth),
+                ];
+                let value = rng.random_range(0..20);
+                grid.fill_box(min, max, &value);
+                for z in min[2]..max[2] {
+                    for y in min[1]..max[1] {
+                        for x in min[0]..max[0] {
+                            control[control_offset(x, y, z)] = value;
+                        }
+                    }
+                }
+            }
+            1 => {
+                let value = rng.random_range(0..20);
+                grid.fill_box([0, 0, 0], [width, height, depth], &value);
+                control.fill(value);
+                assert_eq!(grid.uniform_value(), Some(&value));
+            }
+            _ => {
+                let (x, y, z) = (
+                    rng.random_range(0..width),
+                    rng.random_range(0..height),
+                    rng.random_range(0..depth),
+                );
+                let value = rng.random_range(0..20);
+                grid.set(x, y, z, &value);
+                control[control_offset(x, y, z)] = value;
+            }
+        }
+    }
+
+    for z in 0..depth {
+        for y in 0..height {
+            for x in 0..width {
+                assert_eq!(grid.get(x, y, z), Some(&control[control_offset(x, y, z)]));
+            }
+        }
+    }
+    assert_eq!(grid.get(width, 0, 0), None);
+    assert_eq!(grid.get(0, height, 0), None);
+    assert_eq!(grid.get(0, 0, depth), None);
+
+    let mut visited = 0;
+    for ([x, y, z], value) in grid.iter_with_coords() {
+        assert_eq!(*value, control[control_offset(x, y, z)]);
+        visited += 1;
+    }
+    assert_eq!(visited, control.len());
+
+    for x in 0..width {
+        let control_layer = (0..depth)
+            .flat_map(|z| (0..height).map(move |y| (y, z)))
+            .map(|(y, z)| &control[control_offset(x, y, z)]);
+        assert!(grid.iter_layer(Axis::X, x).eq(control_layer));
+    }
+    for y in 0..height {
+        let control_layer = (0..depth)
+            .flat_map(|z| (0..width).map(move |x| (x, z)))
+            .map(|(x, z)| &control[control_offset(x, y, z)]);
+        assert!(grid.iter_layer(Axis::Y, y).rev().eq(control_layer.rev()));
+    }
+    for z in 0..depth {
+        assert_eq!(grid.iter_layer(Axis::Z, z).len(), width * height);
+        assert!(grid
+            .iter_layer(Axis::Z, z)
+            .eq(control[z * width * height..(z + 1) * width * height].iter()));
+    }
+
+    let uniform = control.iter().all(|value| *value == control[0]);
+    assert_eq!(grid.uniform_value().is_some(), uniform);
+}
+
+fn test_grid2_rng_operations<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let (width, height) = (rng.random_range(1..40), rng.random_range(1..40));
+    let mut grid: PaletteGrid2<u32, P, B> = PaletteGrid2::new(width, height, 0);
+    let mut control = vec![vec![0; width]; height];
+
+    for _ in 0..iteration_count {
+        match rng.random_range(0..10) {
+            0 => {
+                let (source_width, source_height) =
+                    (rng.random_range(0..20), rng.random_range(0..20));
+                let value = rng.random_range(0..20);
+                let mut source: PaletteGrid2<u32, P, B> =
+                    PaletteGrid2::new(source_width, source_height, value);
+                if source_width > 0 && source_height > 0 {
+                    source.set(0, 0, &(value + 1));
+                }
+                let (x, y) = (rng.random_range(0..width + 5), rng.random_range(0..height + 5));
+                grid.blit(&source, x, y);
+                for source_y in 0..source_height {
+                    for source_x in 0..source_width {
+                        if x + source_x < width && y + source_y < height {
+                            control[y + source_y][x + source_x] =
+                                *source.get(source_x, source_y).unwrap();
+                        }
+                    }
+                }
+            }
+            _ => {
+                let (x, y) = (rng.random_range(0..width), rng.random_range(0..height));
+                let value = rng.random_range(0..20);
+                grid.set(x, y, &value);
+                control[y][x] = value;
+            }
+        }
+    }
+
+    assert_eq!(grid.width(), width);
+    assert_eq!(grid.height(), height);
+    assert_eq!(grid.len(), width * height);
+    for (y, row) in control.iter().enumerate() {
+        for (x, value) in row.iter().enumerate() {
+            assert_eq!(grid.get(x, y), Some(value));
+        }
+        assert!(grid.iter_row(y).eq(row.iter()));
+        assert!(grid.row(y).iter().eq(row.iter()));
+    }
+    assert_eq!(grid.get(width, 0), None);
+    assert_eq!(grid.get(0, height), None);
+    assert_eq!(grid.rows().count(), height);
+    for x in 0..width {
+        assert_eq!(grid.iter_column(x).len(), height);
+        assert!(grid.iter_column(x).eq(control.iter().map(|row| &row[x])));
+        assert!(grid.iter_column(x).rev().eq(control.iter().rev().map(|row| &row[x])));
+    }
+
+    for _ in 0..10 {
+        let (x, y) = (rng.random_range(0..=width), rng.random_range(0..=height));
+        let (crop_width, crop_height) =
+            (rng.random_range(0..=width - x), rng.random_range(0..=height - y));
+        let cropped = grid.crop(x, y, crop_width, crop_height);
+        assert_eq!(cropped.width(), crop_width);
+        assert_eq!(cropped.height(), crop_height);
+        for crop_y in 0..crop_height {
+            assert!(cropped
+                .iter_row(crop_y)
+                .eq(control[y + crop_y][x..x + crop_width].iter()));
+        }
+    }
+}
+
+fn color_of(value: &u32) -> [u8; 3] {
+    [*value as u8, (*value >> 8) as u8, (*value >> 16) as u8]
+}
+
+fn value_of(color: [u8; 3]) -> u32 {
+    color[0] as u32 | (color[1] as u32) << 8 | (color[2] as u32) << 16
+}
+
+fn test_grid2_bmp<P, B>(seed: u64)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let (width, height) = (rng.random_range(0..40), rng.random_range(0..40));
+    let amount_unique_values = rng.random_range(1..=256);
+    let mut grid: PaletteGrid2<u32, P, B> = PaletteGrid2::new(width, height, 0);
+    for y in 0..height {
+        for x in 0..width {
+            grid.set(x, y, &(rng.random_range(0..amount_unique_values) * 65599));
+        }
+    }
+    grid.optimize();
+
+    let mut file = Vec::new();
+    grid.write_bmp(&mut file, color_of).unwrap();
+    assert_eq!(&file[..2], b"BM");
+    assert_eq!(u32::from_le_bytes(file[2..6].try_into().unwrap()) as usize, file.len());
+    assert_eq!(u16::from_le_bytes([file[28], file[29]]), 8);
+
+    let read: PaletteGrid2<u32, P, B> = PaletteGrid2::read_bmp(file.as_slice(), value_of).unwrap();
+    assert_eq!(read.width(), width);
+    assert_eq!(read.height(), height);
+    assert!(read.as_palette_vec() == grid.as_palette_vec());
+
+    // Malformed files must fail, never panic
+    for length in 0..file.len().min(200) {
+        assert!(PaletteGrid2::<u32, P, B>::read_bmp(&file[..length], value_of).is_err());
+    }
+    for _ in 0..200 {
+        let mut corrupted = file.clone();
+        let offset = rng.random_range(0..corrupted.len());
+        corrupted[offset] = rng.random();
+        let _ = PaletteGrid2::<u32, P, B>::read_bmp(corrupted.as_slice(), value_of);
+    }
+
+    // More than 256 unique values can't be written
+    let mut too_many: PaletteGrid2<u32, P, B> = PaletteGrid2::new(300, 1, 0);
+    for x in 0..300 {
+        too_many.set(x, 0, &(x as u32));
+    }
+    assert_eq!(
+        too_many.write_bmp(&mut Vec::new(), color_of).unwrap_err().kind(),
+        std::io::ErrorKind::InvalidInput
+    );
+    // But the palette may keep more index slots than that around
+    for x in 0..300 {
+        too_many.set(x, 0, &(x as u32 / 150 * 65599));
+    }
+    too_many.optimize();
+    let mut file = Vec::new();
+    too_many.write_bmp(&mut file, color_of).unwrap();
+    let read: PaletteGrid2<u32, P, B> = PaletteGrid2::read_bmp(file.as_slice(), value_of).unwrap();
+    assert!(read.as_palette_vec() == too_many.as_palette_vec());
+}
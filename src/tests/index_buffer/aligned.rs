@@ -121,3 +121,52 @@ fn index_buffer_iterator() {
         test_index_buffer_iterator(&mut AlignedIndexBuffer::new(), i, 1337);
     }
 }
+
+#[test]
+fn index_buffer_insert_remove() {
+    for i in 0..64 {
+        test_index_buffer_insert_remove(&mut AlignedIndexBuffer::new(), i, 1337);
+    }
+}
+
+#[test]
+fn index_buffer_swap_reverse() {
+    for i in 0..64 {
+        test_index_buffer_swap_reverse(&mut AlignedIndexBuffer::new(), i, 1337);
+    }
+}
+
+#[test]
+fn index_buffer_extend() {
+    for i in 0..64 {
+        test_index_buffer_extend(&mut AlignedIndexBuffer::new(), i, 333);
+    }
+}
+
+#[test]
+fn index_buffer_truncate() {
+    for i in 0..64 {
+        test_index_buffer_truncate(&mut AlignedIndexBuffer::new(), i, 1337);
+    }
+}
+
+#[test]
+fn index_buffer_fill_range() {
+    for i in 0..64 {
+        test_index_buffer_fill_range(&mut AlignedIndexBuffer::new(), i, 1337);
+    }
+}
+
+#[test]
+fn index_buffer_replace_index() {
+    for i in 0..64 {
+        test_index_buffer_replace_index(&mut AlignedIndexBuffer::new(), i, 1337);
+    }
+}
+
+#[test]
+fn index_buffer_iter_double_ended() {
+    for i in 0..64 {
+        test_index_buffer_iter_double_ended(&mut AlignedIndexBuffer::new(), i, 1337);
+    }
+}
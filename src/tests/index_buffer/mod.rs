@@ -1,3 +1,5 @@
+use rustc_hash::FxHashMap;
+
 use crate::index_buffer::IndexBuffer;
 
 mod aligned;
@@ -44,7 +46,7 @@ fn test_index_buffer_pop<B: IndexBuffer>(
 
 fn test_index_buffer_set_index_size_growing<B: IndexBuffer>(
     buffer: &mut B,
-    index_sizes: &mut Vec<usize>,
+    index_sizes: &mut [usize],
     iteration_count: usize,
 ) {
     assert_eq!(buffer.pop_index(), None);
@@ -79,7 +81,7 @@ fn test_index_buffer_set_index_size_growing<B: IndexBuffer>(
 
 fn test_index_buffer_set_index_size_shrinking<B: IndexBuffer>(
     buffer: &mut B,
-    index_sizes: &mut Vec<usize>,
+    index_sizes: &mut [usize],
     iteration_count: usize,
 ) {
     assert_eq!(buffer.pop_index(), None);
@@ -122,7 +124,7 @@ fn test_index_buffer_get<B: IndexBuffer>(
         buffer.push_index(index);
     }
     for i in 0..iteration_count {
-        assert_eq!(buffer.get_index(i as usize), i % possible_different_indices);
+        assert_eq!(buffer.get_index(i), i % possible_different_indices);
     }
     for i in (0..iteration_count).rev() {
         assert_eq!(buffer.pop_index(), Some(i % possible_different_indices));
@@ -145,7 +147,7 @@ fn test_index_buffer_set<B: IndexBuffer>(
     for i in 0..iteration_count {
         let index = (i + 1) % possible_different_indices;
         assert_eq!(
-            buffer.set_index(i as usize, index),
+            buffer.set_index(i, index),
             i % possible_different_indices
         );
     }
@@ -247,3 +249,295 @@ fn test_index_buffer_iterator<B: IndexBuffer>(
         assert_eq!(index, value);
     }
 }
+
+fn test_index_buffer_insert_remove<B: IndexBuffer>(
+    buffer: &mut B,
+    index_size: usize,
+    iteration_count: usize,
+) {
+    assert!(buffer.is_empty());
+    buffer.set_index_size(index_size, None);
+    let possible_different_indices = if index_size > 0 {
+        2 << (index_size - 1)
+    } else {
+        1
+    };
+    let mut control = Vec::new();
+    for i in 0..iteration_count {
+        let index = (i * 7) % possible_different_indices;
+        let offset = (i * 31) % (control.len() + 1);
+        buffer.insert_index(offset, index);
+        control.insert(offset, index);
+        assert_eq!(buffer.len(), control.len());
+    }
+    for (i, index) in control.iter().enumerate() {
+        assert_eq!(buffer.get_index(i), *index);
+    }
+    // Inserting at the end must behave like pushing
+    buffer.insert_index(control.len(), 0);
+    control.push(0);
+    for i in 0..iteration_count {
+        let offset = (i * 13) % control.len();
+        assert_eq!(buffer.remove_index(offset), control.remove(offset));
+        assert_eq!(buffer.len(), control.len());
+    }
+    for (i, index) in control.iter().enumerate() {
+        assert_eq!(buffer.get_index(i), *index);
+    }
+    while let Some(index) = control.pop() {
+        assert_eq!(buffer.pop_index(), Some(index));
+    }
+    assert_eq!(buffer.pop_index(), None);
+}
+
+fn test_index_buffer_swap_reverse<B: IndexBuffer>(
+    buffer: &mut B,
+    index_size: usize,
+    iteration_count: usize,
+) {
+    assert!(buffer.is_empty());
+    buffer.set_index_size(index_size, None);
+    let possible_different_indices = if index_size > 0 {
+        2 << (index_size - 1)
+    } else {
+        1
+    };
+    let mut control = Vec::new();
+    for i in 0..iteration_count {
+        let index = (i * 7) % possible_different_indices;
+        buffer.push_index(index);
+        control.push(index);
+    }
+    for i in 0..iteration_count {
+        let a = (i * 31) % control.len();
+        let b = (i * 17) % control.len();
+        buffer.swap_indices(a, b);
+        control.swap(a, b);
+    }
+    for (i, index) in control.iter().enumerate() {
+        assert_eq!(buffer.get_index(i), *index);
+    }
+    buffer.reverse_indices();
+    control.reverse();
+    for (i, index) in control.iter().enumerate() {
+        assert_eq!(buffer.get_index(i), *index);
+    }
+    buffer.pop_index();
+    control.pop();
+    buffer.reverse_indices();
+    control.reverse();
+    while let Some(index) = control.pop() {
+        assert_eq!(buffer.pop_index(), Some(index));
+    }
+    assert_eq!(buffer.pop_index(), None);
+}
+
+fn test_index_buffer_extend<B: IndexBuffer>(
+    buffer: &mut B,
+    index_size: usize,
+    iteration_count: usize,
+) {
+    assert!(buffer.is_empty());
+    buffer.set_index_size(index_size, None);
+    let possible_different_indices = if index_size > 0 {
+        2 << (index_size - 1)
+    } else {
+        1
+    };
+    let mut control = Vec::new();
+    for i in 0..iteration_count {
+        // Mix single pushes with bulk extends of varying length
+        let chunk = (0..i % 23)
+            .map(|j| (i * 7 + j) % possible_different_indices)
+            .collect::<Vec<_>>();
+        buffer.extend_indices(&chunk);
+        control.extend_from_slice(&chunk);
+        buffer.push_index(i % possible_different_indices);
+        control.push(i % possible_different_indices);
+        assert_eq!(buffer.len(), control.len());
+    }
+    for (i, index) in buffer.iter().enumerate() {
+        assert_eq!(index, control[i]);
+    }
+    while let Some(index) = control.pop() {
+        assert_eq!(buffer.pop_index(), Some(index));
+    }
+    assert_eq!(buffer.pop_index(), None);
+}
+
+fn test_index_buffer_truncate<B: IndexBuffer>(
+    buffer: &mut B,
+    index_size: usize,
+    iteration_count: usize,
+) {
+    assert!(buffer.is_empty());
+    buffer.set_index_size(index_size, None);
+    let possible_different_indices = if index_size > 0 {
+        2 << (index_size - 1)
+    } else {
+        1
+    };
+    let mut control = Vec::new();
+    for i in 0..iteration_count {
+        let index = (i * 7) % possible_different_indices;
+        buffer.push_index(index);
+        control.push(index);
+    }
+    while !control.is_empty() {
+        let new_len = control.len() * 2 / 3;
+        let mut expected_removed = FxHashMap::default();
+        for index in control.drain(new_len..) {
+            *expected_removed.entry(index).or_insert(0) += 1;
+        }
+        assert_eq!(buffer.truncate(new_len), expected_removed);
+        assert_eq!(buffer.len(), control.len());
+        // Pushing after truncating must not see stale indices
+        buffer.push_index(possible_different_indices - 1);
+        assert_eq!(buffer.pop_index(), Some(possible_different_indices - 1));
+        for (i, index) in control.iter().enumerate() {
+            assert_eq!(buffer.get_index(i), *index);
+        }
+    }
+    assert!(buffer.truncate(0).is_empty());
+    assert_eq!(buffer.pop_index(), None);
+}
+
+fn test_index_buffer_fill_range<B: IndexBuffer>(
+    buffer: &mut B,
+    index_size: usize,
+    iteration_count: usize,
+) {
+    assert!(buffer.is_empty());
+    buffer.set_index_size(index_size, None);
+    let possible_different_indices = if index_size > 0 {
+        2 << (index_size - 1)
+    } else {
+        1
+    };
+    let mut control = Vec::new();
+    for i in 0..iteration_count {
+        let index = (i * 7) % possible_different_indices;
+        buffer.push_index(index);
+        control.push(index);
+    }
+    for i in 0..iteration_count / 10 {
+        let start = (i * 31) % control.len();
+        let end = (start + i * 17 % 300).min(control.len());
+        let index = (i * 3) % possible_different_indices;
+        let mut expected_overwritten = FxHashMap::default();
+        for old_index in control[start..end].iter_mut() {
+            *expected_overwritten.entry(*old_index).or_insert(0) += 1;
+            *old_index = index;
+        }
+        assert_eq!(buffer.fill_range(start..end, index), expected_overwritten);
+    }
+    for (i, index) in control.iter().enumerate() {
+        assert_eq!(buffer.get_index(i), *index);
+    }
+    while let Some(index) = control.pop() {
+        assert_eq!(buffer.pop_index(), Some(index));
+    }
+    assert_eq!(buffer.pop_index(), None);
+}
+
+fn test_index_buffer_replace_index<B: IndexBuffer>(
+    buffer: &mut B,
+    index_size: usize,
+    iteration_count: usize,
+) {
+    assert!(buffer.is_empty());
+    buffer.set_index_size(index_size, None);
+    let possible_different_indices = if index_size > 0 {
+        2 << (index_size - 1)
+    } else {
+        1
+    };
+    let mut control = Vec::new();
+    for i in 0..iteration_count {
+        let index = (i * 7) % possible_different_indices.min(13);
+        buffer.push_index(index);
+        control.push(index);
+    }
+    for (from, to) in [(0, 1), (3, 0), (1, 5), (5, 5), (12, 3)] {
+        let from = from % possible_different_indices;
+        let to = to % possible_different_indices;
+        buffer.replace_index(from, to);
+        for index in control.iter_mut() {
+            if *index == from {
+                *index = to;
+            }
+        }
+        for (i, index) in control.iter().enumerate() {
+            assert_eq!(buffer.get_index(i), *index);
+        }
+    }
+}
+
+fn test_index_buffer_iter_double_ended<B: IndexBuffer>(
+    buffer: &mut B,
+    index_size: usize,
+    iteration_count: usize,
+) {
+    assert!(buffer.is_empty());
+    buffer.set_index_size(index_size, None);
+    let possible_different_indices = if index_size > 0 {
+        2 << (index_size - 1)
+    } else {
+        1
+    };
+    let mut control = Vec::new();
+    for i in 0..iteration_count {
+        let index = (i * 7) % possible_different_indices;
+        buffer.push_index(index);
+        control.push(index);
+    }
+
+    assert_eq!(buffer.iter().len(), control.len());
+    assert!(buffer.iter().rev().eq(control.iter().rev().copied()));
+
+    // Alternate between both ends
+    let mut iter = buffer.iter();
+    let mut control_iter = control.iter().copied();
+    for i in 0.. {
+        let (next, control_next) = if i % 3 == 0 {
+            (iter.next_back(), control_iter.next_back())
+        } else {
+            (iter.next(), control_iter.next())
+        };
+        assert_eq!(next, control_next);
+        assert_eq!(iter.len(), control_iter.len());
+        if next.is_none() {
+            break;
+        }
+    }
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next_back(), None);
+
+    // Seeking
+    for step in [0, 1, 5, 31, 64, 129] {
+        let mut iter = buffer.iter();
+        let mut control_iter = control.iter().copied();
+        loop {
+            let next = iter.nth(step);
+            assert_eq!(next, control_iter.nth(step));
+            assert_eq!(iter.nth_back(step / 2), control_iter.nth_back(step / 2));
+            if next.is_none() {
+                break;
+            }
+        }
+    }
+
+    // Ranges
+    for i in 0..iteration_count / 10 {
+        let start = (i * 31) % control.len();
+        let end = (start + i * 17 % 300).min(control.len());
+        assert_eq!(buffer.iter_range(start..end).len(), end - start);
+        assert!(buffer
+            .iter_range(start..end)
+            .eq(control[start..end].iter().copied()));
+        assert!(buffer
+            .iter_range(start..end)
+            .rev()
+            .eq(control[start..end].iter().rev().copied()));
+    }
+}
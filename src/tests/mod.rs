@@ -1,3 +1,4 @@
+mod grid;
 mod index_buffer;
 mod palette;
 mod palette_vec;
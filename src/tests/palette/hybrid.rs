@@ -157,3 +157,31 @@ fn palette_iter_mut() {
     test_palette_iter_mut(HybridPalette::<333, u32>::new(), 20);
     test_palette_iter_mut(HybridPalette::<200, u32>::new(), 43);
 }
+
+#[test]
+fn palette_index_bound() {
+    test_palette_index_bound(HybridPalette::<0, u32>::new(), 2049);
+    test_palette_index_bound(HybridPalette::<1, u32>::new(), 2049);
+    test_palette_index_bound(HybridPalette::<4, u32>::new(), 2049);
+    test_palette_index_bound(HybridPalette::<17, u32>::new(), 2049);
+    test_palette_index_bound(HybridPalette::<333, u32>::new(), 2049);
+    test_palette_index_bound(HybridPalette::<333, u32>::new(), 100);
+}
+
+#[test]
+fn palette_replace_value() {
+    test_palette_replace_value(HybridPalette::<0, u32>::new(), 2049);
+    test_palette_replace_value(HybridPalette::<1, u32>::new(), 2049);
+    test_palette_replace_value(HybridPalette::<4, u32>::new(), 2049);
+    test_palette_replace_value(HybridPalette::<17, u32>::new(), 2049);
+    test_palette_replace_value(HybridPalette::<333, u32>::new(), 100);
+}
+
+#[test]
+fn palette_take_unused() {
+    test_palette_take_unused(HybridPalette::<0, u32>::new(), 2049);
+    test_palette_take_unused(HybridPalette::<1, u32>::new(), 2049);
+    test_palette_take_unused(HybridPalette::<4, u32>::new(), 2049);
+    test_palette_take_unused(HybridPalette::<17, u32>::new(), 2049);
+    test_palette_take_unused(HybridPalette::<333, u32>::new(), 100);
+}
@@ -49,10 +49,10 @@ fn test_pallete_get_by_value<P: Palette<u32>>(mut palette: P, amount_unique_inse
     }
 
     for value in 0..amount_unique_inserts as u32 {
-        /*assert_eq!(
+        assert_eq!(
             palette.get_by_value(&value).map(|x| x.0),
             Some(&PaletteEntry { value, count: 1 })
-        );*/
+        );
         assert_eq!(
             palette.get_mut_by_value(&value).map(|x| x.0),
             Some(&mut PaletteEntry { value, count: 1 })
@@ -99,14 +99,14 @@ fn test_palette_mark_as_unused_len<P: Palette<u32>>(mut palette: P, amount_uniqu
         palette.insert_new(PaletteEntry { value, count: 1 });
     }
 
-    assert_eq!(palette.len(), amount_unique_inserts as usize);
+    assert_eq!(palette.len(), amount_unique_inserts);
 
     for index in 0..amount_unique_inserts {
         palette.get_mut_by_index(index).unwrap().count = 0;
         palette.mark_as_unused(index);
         assert_eq!(
             palette.len(),
-            amount_unique_inserts as usize - index as usize - 1
+            amount_unique_inserts - index - 1
         );
     }
     assert_eq!(palette.len(), 0);
@@ -215,3 +215,80 @@ fn test_palette_iter_mut<P: Palette<u32>>(mut palette: P, amount_unique_inserts:
     }
     assert!(control.is_empty());
 }
+
+fn test_palette_index_bound<P: Palette<u32>>(mut palette: P, amount_unique_inserts: usize) {
+    fn assert_all_entries_below_bound<P: Palette<u32>>(palette: &P) {
+        let found = (0..palette.index_bound())
+            .filter(|index| palette.get_by_index(*index).is_some())
+            .count();
+        assert_eq!(found, palette.len());
+    }
+
+    assert_all_entries_below_bound(&palette);
+    for value in 0..amount_unique_inserts as u32 {
+        palette.insert_new(PaletteEntry { value, count: 1 });
+        assert_all_entries_below_bound(&palette);
+    }
+    for value in (0..amount_unique_inserts as u32).step_by(3) {
+        let (entry, index) = palette.get_mut_by_value(&value).unwrap();
+        entry.count = 0;
+        palette.mark_as_unused(index);
+        assert_all_entries_below_bound(&palette);
+    }
+    for value in (0..amount_unique_inserts as u32).step_by(6) {
+        palette.insert_new(PaletteEntry { value, count: 1 });
+        assert_all_entries_below_bound(&palette);
+    }
+    palette.optimize();
+    assert_all_entries_below_bound(&palette);
+}
+
+fn test_palette_replace_value<P: Palette<u32>>(mut palette: P, amount_unique_inserts: usize) {
+    for value in 0..amount_unique_inserts as u32 {
+        palette.insert_new(PaletteEntry {
+            value,
+            count: value as CountType + 1,
+        });
+    }
+    let offset = amount_unique_inserts as u32;
+    for value in (0..amount_unique_inserts as u32).step_by(2) {
+        let (_, index) = palette.get_by_value(&value).unwrap();
+        assert_eq!(palette.replace_value(index, value + offset), value);
+        assert!(palette.get_by_value(&value).is_none());
+        assert_eq!(
+            palette.get_by_value(&(value + offset)),
+            Some((
+                &PaletteEntry {
+                    value: value + offset,
+                    count: value as CountType + 1
+                },
+                index
+            ))
+        );
+    }
+    assert_eq!(palette.len(), amount_unique_inserts);
+    // The old values must be insertable again
+    for value in (0..amount_unique_inserts as u32).step_by(2) {
+        palette.insert_new(PaletteEntry { value, count: 1 });
+        assert!(palette.get_by_value(&value).is_some());
+    }
+}
+
+fn test_palette_take_unused<P: Palette<u32>>(mut palette: P, amount_unique_inserts: usize) {
+    for value in 0..amount_unique_inserts as u32 {
+        palette.insert_new(PaletteEntry { value, count: 1 });
+    }
+    for value in (0..amount_unique_inserts as u32).step_by(2) {
+        let (entry, index) = palette.get_mut_by_value(&value).unwrap();
+        entry.count = 0;
+        assert_eq!(palette.take_unused(index), value);
+        assert!(palette.get_by_value(&value).is_none());
+        assert!(palette.get_by_index(index).is_none());
+    }
+    assert_eq!(palette.len(), amount_unique_inserts / 2);
+    // Freed slots must be reusable
+    for value in (0..amount_unique_inserts as u32).step_by(2) {
+        palette.insert_new(PaletteEntry { value, count: 1 });
+    }
+    assert_eq!(palette.len(), amount_unique_inserts);
+}
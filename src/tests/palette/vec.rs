@@ -56,3 +56,18 @@ fn palette_iter() {
 fn palette_iter_mut() {
     test_palette_iter_mut(VecPalette::new(), 16);
 }
+
+#[test]
+fn palette_index_bound() {
+    test_palette_index_bound(VecPalette::new(), 2049);
+}
+
+#[test]
+fn palette_replace_value() {
+    test_palette_replace_value(VecPalette::new(), 2049);
+}
+
+#[test]
+fn palette_take_unused() {
+    test_palette_take_unused(VecPalette::new(), 2049);
+}
@@ -202,3 +202,338 @@ fn palette_vec_palette_iter_mut() {
     test_palette_vec_palette_iter_mut::<HybridPalette<20, u32>, AlignedIndexBuffer>(100, 1337);
     test_palette_vec_palette_iter_mut::<HybridPalette<1, u32>, AlignedIndexBuffer>(1000, 1337);
 }
+
+#[test]
+fn palette_vec_insert_remove() {
+    let mut rng = ChaCha8Rng::seed_from_u64(8723645982734);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_insert_remove::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_insert_remove::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_insert_remove::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_insert_remove::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_insert_remove::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 3333);
+    }
+}
+
+#[test]
+fn palette_vec_swap_reverse() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1290837461234);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_swap_reverse::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_swap_reverse::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_swap_reverse::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_swap_reverse::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_swap_reverse::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 3333);
+    }
+}
+
+#[test]
+fn palette_vec_from_iter_extend() {
+    test_palette_vec_from_iter_extend::<HybridPalette<0, u32>, AlignedIndexBuffer>(1, 1337);
+    test_palette_vec_from_iter_extend::<HybridPalette<1, u32>, AlignedIndexBuffer>(2, 1337);
+    test_palette_vec_from_iter_extend::<HybridPalette<4, u32>, AlignedIndexBuffer>(7, 1337);
+    test_palette_vec_from_iter_extend::<HybridPalette<16, u32>, AlignedIndexBuffer>(33, 1337);
+    test_palette_vec_from_iter_extend::<HybridPalette<64, u32>, AlignedIndexBuffer>(200, 4096);
+    test_palette_vec_from_iter_extend::<HybridPalette<64, u32>, AlignedIndexBuffer>(1000, 1337);
+}
+
+#[test]
+fn palette_vec_split_append() {
+    let mut rng = ChaCha8Rng::seed_from_u64(5982734129823);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_split_append::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_split_append::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_split_append::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_split_append::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_split_append::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 3333);
+    }
+}
+
+#[test]
+fn palette_vec_fill_set_range() {
+    let mut rng = ChaCha8Rng::seed_from_u64(7812364512387);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_fill_set_range::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 1333);
+        test_palette_vec_fill_set_range::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 1333);
+        test_palette_vec_fill_set_range::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 1333);
+        test_palette_vec_fill_set_range::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 1333);
+        test_palette_vec_fill_set_range::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 1333);
+    }
+}
+
+#[test]
+fn palette_vec_replace() {
+    let mut rng = ChaCha8Rng::seed_from_u64(2340981723409);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_replace::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_replace::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_replace::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_replace::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_replace::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 3333);
+    }
+}
+
+#[test]
+fn palette_vec_contains_count_of() {
+    test_palette_vec_contains_count_of::<HybridPalette<0, u32>, AlignedIndexBuffer>(1, 1337);
+    test_palette_vec_contains_count_of::<HybridPalette<1, u32>, AlignedIndexBuffer>(3, 1337);
+    test_palette_vec_contains_count_of::<HybridPalette<4, u32>, AlignedIndexBuffer>(9, 1337);
+    test_palette_vec_contains_count_of::<HybridPalette<17, u32>, AlignedIndexBuffer>(17, 1337);
+    test_palette_vec_contains_count_of::<HybridPalette<64, u32>, AlignedIndexBuffer>(333, 1337);
+}
+
+#[test]
+fn palette_vec_replace_all() {
+    let mut rng = ChaCha8Rng::seed_from_u64(9812734098123);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_replace_all::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_replace_all::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_replace_all::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_replace_all::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_replace_all::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 3333);
+    }
+}
+
+#[test]
+fn palette_vec_map() {
+    test_palette_vec_map::<HybridPalette<0, u32>, AlignedIndexBuffer>(1, 1, 1337);
+    test_palette_vec_map::<HybridPalette<1, u32>, AlignedIndexBuffer>(7, 1, 1337);
+    test_palette_vec_map::<HybridPalette<4, u32>, AlignedIndexBuffer>(7, 100, 1337);
+    test_palette_vec_map::<HybridPalette<16, u32>, AlignedIndexBuffer>(33, 3, 1337);
+    test_palette_vec_map::<HybridPalette<64, u32>, AlignedIndexBuffer>(333, 17, 1337);
+    test_palette_vec_map::<HybridPalette<64, u32>, AlignedIndexBuffer>(333, 1000, 1337);
+}
+
+#[test]
+fn palette_vec_iter_double_ended() {
+    test_palette_vec_iter_double_ended::<HybridPalette<0, u32>, AlignedIndexBuffer>(1, 1337);
+    test_palette_vec_iter_double_ended::<HybridPalette<1, u32>, AlignedIndexBuffer>(3, 1337);
+    test_palette_vec_iter_double_ended::<HybridPalette<4, u32>, AlignedIndexBuffer>(9, 1337);
+    test_palette_vec_iter_double_ended::<HybridPalette<17, u32>, AlignedIndexBuffer>(17, 1337);
+    test_palette_vec_iter_double_ended::<HybridPalette<64, u32>, AlignedIndexBuffer>(333, 1337);
+}
+
+#[test]
+fn palette_vec_iter_runs() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1203981273);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_iter_runs::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_iter_runs::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_iter_runs::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_iter_runs::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_iter_runs::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 3333);
+    }
+}
+
+#[test]
+fn palette_vec_slice() {
+    let mut rng = ChaCha8Rng::seed_from_u64(5629834756);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_slice::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 1337);
+        test_palette_vec_slice::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 1337);
+        test_palette_vec_slice::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 1337);
+        test_palette_vec_slice::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 1337);
+        test_palette_vec_slice::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 1337);
+    }
+}
+
+#[test]
+fn palette_vec_into_iter_drain() {
+    let mut rng = ChaCha8Rng::seed_from_u64(3498572093);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_into_iter_drain::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 1337);
+        test_palette_vec_into_iter_drain::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 1337);
+        test_palette_vec_into_iter_drain::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 1337);
+        test_palette_vec_into_iter_drain::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 1337);
+        test_palette_vec_into_iter_drain::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 1337);
+    }
+}
+
+#[test]
+fn palette_vec_into_iter_moves_last() {
+    test_palette_vec_into_iter_moves_last::<HybridPalette<0, CloneCounted>, AlignedIndexBuffer>(1, 1337);
+    test_palette_vec_into_iter_moves_last::<HybridPalette<4, CloneCounted>, AlignedIndexBuffer>(3, 1337);
+    test_palette_vec_into_iter_moves_last::<HybridPalette<4, CloneCounted>, AlignedIndexBuffer>(33, 1337);
+    test_palette_vec_into_iter_moves_last::<HybridPalette<64, CloneCounted>, AlignedIndexBuffer>(33, 1337);
+}
+
+#[test]
+fn palette_vec_eq_hash_ord() {
+    let mut rng = ChaCha8Rng::seed_from_u64(7765123409);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_eq_hash_ord::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 1337);
+        test_palette_vec_eq_hash_ord::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 1337);
+        test_palette_vec_eq_hash_ord::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 1337);
+        test_palette_vec_eq_hash_ord::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 1337);
+        test_palette_vec_eq_hash_ord::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 1337);
+    }
+}
+
+#[test]
+fn palette_vec_vec_conversions() {
+    let mut rng = ChaCha8Rng::seed_from_u64(2384751203);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_vec_conversions::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_vec_conversions::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_vec_conversions::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_vec_conversions::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_vec_conversions::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 3333);
+    }
+}
+
+#[test]
+fn palette_vec_sort() {
+    let mut rng = ChaCha8Rng::seed_from_u64(6612093847);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_sort::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_sort::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_sort::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_sort::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 3333);
+        test_palette_vec_sort::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 3333);
+    }
+}
+
+#[test]
+fn palette_vec_packed_longs() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1164109872);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_packed_longs::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_packed_longs::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_packed_longs::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_packed_longs::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_packed_longs::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 4096);
+    }
+}
+
+#[test]
+fn palette_vec_varint_data() {
+    let mut rng = ChaCha8Rng::seed_from_u64(2093418876);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_varint_data::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_varint_data::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_varint_data::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_varint_data::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_varint_data::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 4096);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn palette_vec_canonical_serde() {
+    let mut rng = ChaCha8Rng::seed_from_u64(3391827760);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_canonical_serde::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_canonical_serde::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_canonical_serde::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_canonical_serde::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_canonical_serde::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 4096);
+    }
+}
+
+#[test]
+fn palette_vec_check_invariants() {
+    let mut rng = ChaCha8Rng::seed_from_u64(5571093386);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_check_invariants::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 2048);
+        test_palette_vec_check_invariants::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 2048);
+        test_palette_vec_check_invariants::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 2048);
+        test_palette_vec_check_invariants::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 2048);
+        test_palette_vec_check_invariants::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 2048);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn palette_vec_validated_serde() {
+    let mut rng = ChaCha8Rng::seed_from_u64(7720391145);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_validated_serde::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 2048);
+        test_palette_vec_validated_serde::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 2048);
+        test_palette_vec_validated_serde::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 2048);
+        test_palette_vec_validated_serde::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 2048);
+        test_palette_vec_validated_serde::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 2048);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn palette_vec_validated_serde_free_indices() {
+    type Pv = PaletteVec<u32, HybridPalette<0, u32>, AlignedIndexBuffer>;
+    let pv: Pv = [5, 6].as_slice().into();
+    let mut json = serde_json::to_value(&pv).unwrap();
+    let free_indices = &mut json["palette"]["storage"]["HashMap"]["free_indices"];
+    assert_eq!(*free_indices, serde_json::json!([]));
+    // Free indices that don't fit into the index size of 1 would be masked in the buffer
+    *free_indices = serde_json::json!([2, 3]);
+    assert!(serde_json::from_value::<Pv>(json).is_err());
+}
+
+#[cfg(feature = "bitcode")]
+#[test]
+fn palette_vec_validated_bitcode() {
+    let mut rng = ChaCha8Rng::seed_from_u64(3391047726);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_validated_bitcode::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 2048);
+        test_palette_vec_validated_bitcode::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 2048);
+        test_palette_vec_validated_bitcode::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 2048);
+        test_palette_vec_validated_bitcode::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 2048);
+        test_palette_vec_validated_bitcode::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 2048);
+    }
+}
+
+#[test]
+fn palette_vec_native_format() {
+    let mut rng = ChaCha8Rng::seed_from_u64(8840127735);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_native_format::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_native_format::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_native_format::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_native_format::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_native_format::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 4096);
+    }
+}
+
+#[test]
+fn palette_vec_ref() {
+    let mut rng = ChaCha8Rng::seed_from_u64(4417209383);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_ref::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_ref::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_ref::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_ref::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_ref::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 4096);
+    }
+}
+
+#[test]
+fn palette_vec_convert() {
+    let mut rng = ChaCha8Rng::seed_from_u64(6120938477);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_convert::<HybridPalette<0, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_convert::<HybridPalette<1, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_convert::<HybridPalette<4, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_convert::<HybridPalette<17, u32>, AlignedIndexBuffer>(seed, 4096);
+        test_palette_vec_convert::<HybridPalette<199, u32>, AlignedIndexBuffer>(seed, 4096);
+    }
+}
@@ -70,3 +70,226 @@ fn palette_vec_palette_iter() {
 fn palette_vec_palette_iter_mut() {
     test_palette_vec_palette_iter_mut::<VecPalette<u32>, FastIndexBuffer>(1, 1337);
 }
+
+#[test]
+fn palette_vec_insert_remove() {
+    let mut rng = ChaCha8Rng::seed_from_u64(8723645982734);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_insert_remove::<VecPalette<u32>, FastIndexBuffer>(seed, 3333);
+    }
+}
+
+#[test]
+fn palette_vec_swap_reverse() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1290837461234);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_swap_reverse::<VecPalette<u32>, FastIndexBuffer>(seed, 3333);
+    }
+}
+
+#[test]
+fn palette_vec_from_iter_extend() {
+    test_palette_vec_from_iter_extend::<VecPalette<u32>, FastIndexBuffer>(1, 1337);
+    test_palette_vec_from_iter_extend::<VecPalette<u32>, FastIndexBuffer>(200, 4096);
+}
+
+#[test]
+fn palette_vec_split_append() {
+    let mut rng = ChaCha8Rng::seed_from_u64(5982734129823);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_split_append::<VecPalette<u32>, FastIndexBuffer>(seed, 3333);
+    }
+}
+
+#[test]
+fn palette_vec_fill_set_range() {
+    let mut rng = ChaCha8Rng::seed_from_u64(7812364512387);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_fill_set_range::<VecPalette<u32>, FastIndexBuffer>(seed, 1333);
+    }
+}
+
+#[test]
+fn palette_vec_replace() {
+    let mut rng = ChaCha8Rng::seed_from_u64(2340981723409);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_replace::<VecPalette<u32>, FastIndexBuffer>(seed, 3333);
+    }
+}
+
+#[test]
+fn palette_vec_contains_count_of() {
+    test_palette_vec_contains_count_of::<VecPalette<u32>, FastIndexBuffer>(33, 1337);
+}
+
+#[test]
+fn palette_vec_replace_all() {
+    let mut rng = ChaCha8Rng::seed_from_u64(9812734098123);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_replace_all::<VecPalette<u32>, FastIndexBuffer>(seed, 3333);
+    }
+}
+
+#[test]
+fn palette_vec_map() {
+    test_palette_vec_map::<VecPalette<u32>, FastIndexBuffer>(1, 1, 1337);
+    test_palette_vec_map::<VecPalette<u32>, FastIndexBuffer>(333, 17, 1337);
+    test_palette_vec_map::<VecPalette<u32>, FastIndexBuffer>(333, 1000, 1337);
+}
+
+#[test]
+fn palette_vec_iter_double_ended() {
+    test_palette_vec_iter_double_ended::<VecPalette<u32>, FastIndexBuffer>(1, 1337);
+    test_palette_vec_iter_double_ended::<VecPalette<u32>, FastIndexBuffer>(33, 1337);
+    test_palette_vec_iter_double_ended::<VecPalette<u32>, FastIndexBuffer>(333, 1337);
+}
+
+#[test]
+fn palette_vec_iter_runs() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1203981273);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_iter_runs::<VecPalette<u32>, FastIndexBuffer>(seed, 3333);
+    }
+}
+
+#[test]
+fn palette_vec_slice() {
+    let mut rng = ChaCha8Rng::seed_from_u64(5629834756);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_slice::<VecPalette<u32>, FastIndexBuffer>(seed, 1337);
+    }
+}
+
+#[test]
+fn palette_vec_into_iter_drain() {
+    let mut rng = ChaCha8Rng::seed_from_u64(3498572093);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_into_iter_drain::<VecPalette<u32>, FastIndexBuffer>(seed, 1337);
+    }
+}
+
+#[test]
+fn palette_vec_into_iter_moves_last() {
+    test_palette_vec_into_iter_moves_last::<VecPalette<CloneCounted>, FastIndexBuffer>(1, 1337);
+    test_palette_vec_into_iter_moves_last::<VecPalette<CloneCounted>, FastIndexBuffer>(33, 1337);
+}
+
+#[test]
+fn palette_vec_eq_hash_ord() {
+    let mut rng = ChaCha8Rng::seed_from_u64(7765123409);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_eq_hash_ord::<VecPalette<u32>, FastIndexBuffer>(seed, 1337);
+    }
+}
+
+#[test]
+fn palette_vec_vec_conversions() {
+    let mut rng = ChaCha8Rng::seed_from_u64(2384751203);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_vec_conversions::<VecPalette<u32>, FastIndexBuffer>(seed, 3333);
+    }
+}
+
+#[test]
+fn palette_vec_sort() {
+    let mut rng = ChaCha8Rng::seed_from_u64(6612093847);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_sort::<VecPalette<u32>, FastIndexBuffer>(seed, 3333);
+    }
+}
+
+#[test]
+fn palette_vec_packed_longs() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1164109872);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_packed_longs::<VecPalette<u32>, FastIndexBuffer>(seed, 4096);
+    }
+}
+
+#[test]
+fn palette_vec_varint_data() {
+    let mut rng = ChaCha8Rng::seed_from_u64(2093418876);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_varint_data::<VecPalette<u32>, FastIndexBuffer>(seed, 4096);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn palette_vec_canonical_serde() {
+    let mut rng = ChaCha8Rng::seed_from_u64(3391827760);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_canonical_serde::<VecPalette<u32>, FastIndexBuffer>(seed, 4096);
+    }
+}
+
+#[test]
+fn palette_vec_check_invariants() {
+    let mut rng = ChaCha8Rng::seed_from_u64(5571093386);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_check_invariants::<VecPalette<u32>, FastIndexBuffer>(seed, 2048);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn palette_vec_validated_serde() {
+    let mut rng = ChaCha8Rng::seed_from_u64(7720391145);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_validated_serde::<VecPalette<u32>, FastIndexBuffer>(seed, 2048);
+    }
+}
+
+#[cfg(feature = "bitcode")]
+#[test]
+fn palette_vec_validated_bitcode() {
+    let mut rng = ChaCha8Rng::seed_from_u64(3391047726);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_validated_bitcode::<VecPalette<u32>, FastIndexBuffer>(seed, 2048);
+    }
+}
+
+#[test]
+fn palette_vec_native_format() {
+    let mut rng = ChaCha8Rng::seed_from_u64(8840127735);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_native_format::<VecPalette<u32>, FastIndexBuffer>(seed, 4096);
+    }
+}
+
+#[test]
+fn palette_vec_ref() {
+    let mut rng = ChaCha8Rng::seed_from_u64(4417209383);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_ref::<VecPalette<u32>, FastIndexBuffer>(seed, 4096);
+    }
+}
+
+#[test]
+fn palette_vec_convert() {
+    let mut rng = ChaCha8Rng::seed_from_u64(6120938477);
+    for _ in 0..calc_rng_iterations(8) {
+        let seed = rng.random();
+        test_palette_vec_convert::<VecPalette<u32>, FastIndexBuffer>(seed, 4096);
+    }
+}
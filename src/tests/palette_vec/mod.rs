@@ -2,7 +2,11 @@ use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
 use rustc_hash::FxHashMap;
 
-use crate::{index_buffer::IndexBuffer, palette::Palette, PaletteVec};
+use crate::{
+    index_buffer::IndexBuffer,
+    palette::{count_to_usize, vec::VecPalette, Palette},
+    PaletteVec,
+};
 
 use super::calc_rng_iterations;
 
@@ -136,7 +140,7 @@ where
     }
     for i in 0..iteration_count {
         let value = (i + 1) % amount_unique_values;
-        pv.set(i as usize, &(value as u32));
+        pv.set(i, &(value as u32));
     }
     for i in (0..iteration_count).rev() {
         let value = (i + 1) % amount_unique_values;
@@ -150,7 +154,7 @@ where
         pv.push(value as u32);
     }
     for i in 0..iteration_count {
-        pv.set(i as usize, &0);
+        pv.set(i, &0);
     }
     for _ in 0..iteration_count {
         assert_eq!(pv.pop(), Some(0));
@@ -166,7 +170,7 @@ where
     }
     for i in 0..iteration_count {
         let value = i % amount_unique_values;
-        pv.set(i as usize, &(value as u32));
+        pv.set(i, &(value as u32));
         assert_eq!(pv.get(i), Some(&(value as u32)));
     }
     for i in (0..iteration_count).rev() {
@@ -213,7 +217,7 @@ where
     assert_eq!(pv.get(iteration_count), None);
     for i in 0..iteration_count {
         let value = i % 11;
-        pv.set(i as usize, &(value as u32));
+        pv.set(i, &(value as u32));
         assert_eq!(pv.get(i), Some(&(value as u32)));
     }
 }
@@ -240,7 +244,7 @@ where
     assert_eq!(pv.unique_values(), 2);
     assert_eq!(pv.len(), iteration_count);
     for i in 0..iteration_count {
-        pv.set(i as usize, &(i as u32 % 77));
+        pv.set(i, &(i as u32 % 77));
     }
     assert_eq!(pv.unique_values(), 77);
     assert_eq!(pv.len(), iteration_count);
@@ -252,7 +256,7 @@ where
     }
     for i in 0..iteration_count {
         if (i % 77) % 3 == 0 {
-            pv.set(i as usize, &0);
+            pv.set(i, &0);
         }
     }
     assert_eq!(pv.unique_values(), 52);
@@ -269,7 +273,7 @@ where
     }
     for i in 0..iteration_count {
         if (i % 77) % 2 == 0 {
-            pv.set(i as usize, &0);
+            pv.set(i, &0);
         }
     }
     assert_eq!(pv.unique_values(), 26);
@@ -326,13 +330,13 @@ where
         if rng.random_bool(0.33) {
             assert_eq!(pv.pop(), control.pop());
         }
-        if rng.random_bool(0.5) && pv.len() > 0 {
+        if rng.random_bool(0.5) && !pv.is_empty() {
             let index = rng.random_range(0..pv.len());
             let n = rng.random_range(0..max_elem);
             pv.set(index, &n);
             control[index] = n;
         }
-        if rng.random_bool(0.5) && pv.len() > 0 {
+        if rng.random_bool(0.5) && !pv.is_empty() {
             let index = rng.random_range(0..pv.len());
             assert_eq!(pv.get(index), control.get(index));
         }
@@ -343,6 +347,7 @@ where
     }
 }
 
+#[allow(clippy::explicit_counter_loop)]
 fn test_palette_vec_iter<P, B>(amount_unique_values: usize, iteration_count: usize)
 where
     P: Palette<u32>,
@@ -411,3 +416,1593 @@ where
         assert_eq!(entry.count, *control_count);
     }
 }
+
+fn test_palette_vec_insert_remove<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    let iteration_count = calc_rng_iterations(iteration_count);
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut pv: PaletteVec<u32, P, B> = PaletteVec::new();
+    let mut control = Vec::new();
+    let max_elem = 333;
+
+    for _ in 0..iteration_count {
+        if rng.random_bool(0.01) {
+            pv.optimize();
+        }
+        if rng.random_bool(0.5) {
+            let offset = rng.random_range(0..=control.len());
+            let n = rng.random_range(0..max_elem);
+            pv.insert(offset, &n);
+            control.insert(offset, n);
+        }
+        if rng.random_bool(0.3) && !control.is_empty() {
+            let offset = rng.random_range(0..control.len());
+            assert_eq!(pv.remove(offset), control.remove(offset));
+        }
+        assert_eq!(pv.len(), control.len());
+    }
+    for (i, value) in control.iter().enumerate() {
+        assert_eq!(pv.get(i), Some(value));
+    }
+    while !control.is_empty() {
+        assert_eq!(pv.remove(0), control.remove(0));
+    }
+    assert_eq!(pv.unique_values(), 0);
+}
+
+fn test_palette_vec_swap_reverse<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    let iteration_count = calc_rng_iterations(iteration_count);
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut pv: PaletteVec<u32, P, B> = PaletteVec::new();
+    let mut control = Vec::new();
+    let max_elem = 333;
+
+    for _ in 0..iteration_count {
+        if rng.random_bool(0.5) {
+            let n = rng.random_range(0..max_elem);
+            pv.push(n);
+            control.push(n);
+        }
+        if rng.random_bool(0.3) && !control.is_empty() {
+            let a = rng.random_range(0..control.len());
+            let b = rng.random_range(0..control.len());
+            pv.swap(a, b);
+            control.swap(a, b);
+        }
+        if rng.random_bool(0.2) && !control.is_empty() {
+            let offset = rng.random_range(0..control.len());
+            assert_eq!(pv.swap_remove(offset), control.swap_remove(offset));
+        }
+        if rng.random_bool(0.01) {
+            pv.reverse();
+            control.reverse();
+        }
+        assert_eq!(pv.len(), control.len());
+    }
+    for (i, value) in control.iter().enumerate() {
+        assert_eq!(pv.get(i), Some(value));
+    }
+    while !control.is_empty() {
+        assert_eq!(pv.swap_remove(0), control.swap_remove(0));
+    }
+    assert_eq!(pv.unique_values(), 0);
+
+    // Reordering with a single unique value does not touch the buffer
+    let mut pv: PaletteVec<u32, P, B> = PaletteVec::filled(3, 10);
+    pv.swap(0, 9);
+    pv.reverse();
+    assert_eq!(pv.swap_remove(4), 3);
+    assert_eq!(pv.len(), 9);
+}
+
+fn test_palette_vec_from_iter_extend<P, B>(amount_unique_values: usize, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    let control = (0..iteration_count)
+        .map(|i| ((i * 13) % amount_unique_values) as u32)
+        .collect::<Vec<_>>();
+
+    let pv: PaletteVec<u32, P, B> = control.iter().copied().collect();
+    assert_eq!(pv.len(), control.len());
+    assert_eq!(pv.unique_values(), amount_unique_values.min(iteration_count));
+    for (i, value) in pv.iter().enumerate() {
+        assert_eq!(*value, control[i]);
+    }
+
+    let mut pv: PaletteVec<u32, P, B> = PaletteVec::new();
+    let (head, tail) = control.split_at(iteration_count / 3);
+    pv.extend_from_slice(head);
+    pv.extend(tail.iter());
+    pv.extend(control.iter().copied());
+    assert_eq!(pv.len(), control.len() * 2);
+    for (i, value) in pv.iter().enumerate() {
+        assert_eq!(*value, control[i % control.len()]);
+    }
+    for entry in pv.iter_palette_entries() {
+        let control_count = control.iter().filter(|value| **value == entry.value).count();
+        assert_eq!(count_to_usize(entry.count), control_count * 2);
+    }
+    while let Some(value) = pv.pop() {
+        assert_eq!(value, control[pv.len() % control.len()]);
+    }
+    assert_eq!(pv.unique_values(), 0);
+}
+
+fn test_palette_vec_split_append<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    let iteration_count = calc_rng_iterations(iteration_count);
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut pv: PaletteVec<u32, P, B> = PaletteVec::new();
+    let mut control = Vec::new();
+    let max_elem = rng.random_range(1..333);
+
+    for _ in 0..iteration_count {
+        let n = rng.random_range(0..max_elem);
+        pv.push(n);
+        control.push(n);
+        if rng.random_bool(0.01) {
+            let len = rng.random_range(0..=control.len());
+            pv.truncate(len);
+            control.truncate(len);
+        }
+        if rng.random_bool(0.01) {
+            let at = rng.random_range(0..=control.len());
+            let mut tail = pv.split_off(at);
+            let mut control_tail = control.split_off(at);
+            assert_eq!(pv.len(), control.len());
+            assert_eq!(tail.len(), control_tail.len());
+            for (i, value) in tail.iter().enumerate() {
+                assert_eq!(*value, control_tail[i]);
+            }
+            // Modify the tail to diverge the palettes before merging them back
+            for _ in 0..rng.random_range(0..10) {
+                let n = rng.random_range(0..max_elem * 2);
+                tail.push(n);
+                control_tail.push(n);
+            }
+            pv.append(&mut tail);
+            control.append(&mut control_tail);
+            assert!(tail.is_empty());
+            assert_eq!(tail.unique_values(), 0);
+        }
+        assert_eq!(pv.len(), control.len());
+    }
+    for (i, value) in pv.iter().enumerate() {
+        assert_eq!(*value, control[i]);
+    }
+
+    let third = control.len() / 3;
+    let parts = [
+        pv.split_off(2 * third),
+        pv.split_off(third),
+        pv,
+    ];
+    let concatenated: PaletteVec<u32, P, B> = PaletteVec::concat(parts.iter().rev());
+    assert_eq!(concatenated.len(), control.len());
+    for (i, value) in concatenated.iter().enumerate() {
+        assert_eq!(*value, control[i]);
+    }
+    for entry in concatenated.iter_palette_entries() {
+        let control_count = control.iter().filter(|value| **value == entry.value).count();
+        assert_eq!(count_to_usize(entry.count), control_count);
+    }
+    let concatenated: PaletteVec<u32, P, B> = PaletteVec::concat(parts);
+    assert_eq!(concatenated.len(), control.len());
+}
+
+fn test_palette_vec_fill_set_range<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    let iteration_count = calc_rng_iterations(iteration_count);
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut pv: PaletteVec<u32, P, B> = PaletteVec::filled(0, 1000);
+    let mut control = vec![0; 1000];
+    let max_elem = rng.random_range(1..333);
+
+    for _ in 0..iteration_count {
+        if rng.random_bool(0.01) {
+            pv.optimize();
+        }
+        if rng.random_bool(0.3) {
+            let n = rng.random_range(0..max_elem);
+            pv.push(n);
+            control.push(n);
+        }
+        if rng.random_bool(0.2) {
+            let start = rng.random_range(0..=control.len());
+            let end = rng.random_range(start..=control.len());
+            let n = rng.random_range(0..max_elem);
+            pv.fill_range(start..end, &n);
+            control[start..end].fill(n);
+        }
+        if rng.random_bool(0.2) {
+            let start = rng.random_range(0..=control.len());
+            let end = rng.random_range(start..=control.len());
+            let values = (start..end)
+                .map(|_| {
+                    if rng.random_bool(0.8) {
+                        max_elem
+                    } else {
+                        rng.random_range(0..max_elem * 2)
+                    }
+                })
+                .collect::<Vec<_>>();
+            pv.set_range(start, &values);
+            control[start..end].copy_from_slice(&values);
+        }
+        if rng.random_bool(0.005) {
+            let n = rng.random_range(0..max_elem);
+            pv.fill(n);
+            control.fill(n);
+            assert_eq!(pv.unique_values(), 1);
+        }
+        assert_eq!(pv.len(), control.len());
+    }
+    for (i, value) in pv.iter().enumerate() {
+        assert_eq!(*value, control[i]);
+    }
+    for entry in pv.iter_palette_entries() {
+        let control_count = control.iter().filter(|value| **value == entry.value).count();
+        assert_eq!(count_to_usize(entry.count), control_count);
+    }
+
+    pv.fill_range(.., &7);
+    pv.fill_range(..=0, &7);
+    pv.set_range(0, &[7, 7, 7]);
+    assert_eq!(pv.unique_values(), 1);
+    assert!(pv.iter().all(|value| *value == 7));
+}
+
+fn test_palette_vec_replace<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    let iteration_count = calc_rng_iterations(iteration_count);
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut pv: PaletteVec<u32, P, B> = PaletteVec::filled(5, 3);
+    let mut control = vec![5; 3];
+    let max_elem = rng.random_range(1..333);
+
+    assert_eq!(pv.replace(1, &5), 5);
+    assert_eq!(pv.unique_values(), 1);
+    assert_eq!(pv.try_replace(3, &5), None);
+
+    for _ in 0..iteration_count {
+        if rng.random_bool(0.01) {
+            pv.optimize();
+        }
+        if rng.random_bool(0.3) {
+            let n = rng.random_range(0..max_elem);
+            pv.push(n);
+            control.push(n);
+        }
+        if rng.random_bool(0.2) && !control.is_empty() {
+            assert_eq!(pv.pop(), control.pop());
+        }
+        if rng.random_bool(0.5) {
+            let offset = rng.random_range(0..control.len() + 1);
+            let n = rng.random_range(0..max_elem);
+            let old_value = control.get_mut(offset).map(|old| std::mem::replace(old, n));
+            assert_eq!(pv.try_replace(offset, &n), old_value);
+        }
+        assert_eq!(pv.len(), control.len());
+    }
+    for (i, value) in control.iter().enumerate() {
+        assert_eq!(pv.replace(i, &max_elem), *value);
+    }
+    assert_eq!(pv.unique_values(), 1);
+}
+
+fn test_palette_vec_contains_count_of<P, B>(amount_unique_values: usize, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    let mut pv: PaletteVec<u32, P, B> = PaletteVec::new();
+    assert!(!pv.contains(&0));
+    assert_eq!(pv.count_of(&0), 0);
+    assert_eq!(pv.histogram().count(), 0);
+    let mut control = FxHashMap::default();
+    for i in 0..iteration_count {
+        let value = ((i * i) % amount_unique_values) as u32;
+        pv.push(value);
+        *control.entry(value).or_insert(0) += 1;
+    }
+    for value in 0..amount_unique_values as u32 + 10 {
+        let control_count = control.get(&value).copied().unwrap_or(0);
+        assert_eq!(pv.contains(&value), control_count > 0);
+        assert_eq!(pv.count_of(&value), control_count);
+    }
+    let mut histogram = pv.histogram().collect::<Vec<_>>();
+    histogram.sort();
+    let mut control_histogram = control.iter().map(|(v, c)| (v, *c)).collect::<Vec<_>>();
+    control_histogram.sort();
+    assert_eq!(histogram, control_histogram);
+
+    while let Some(value) = pv.pop() {
+        let control_count = control.get_mut(&value).unwrap();
+        *control_count -= 1;
+        assert_eq!(pv.count_of(&value), *control_count);
+        assert_eq!(pv.contains(&value), *control_count > 0);
+    }
+}
+
+fn test_palette_vec_replace_all<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    let iteration_count = calc_rng_iterations(iteration_count);
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut pv: PaletteVec<u32, P, B> = PaletteVec::new();
+    let mut control = Vec::new();
+    let max_elem = rng.random_range(1..100);
+
+    assert_eq!(pv.replace_all(&0, 1), 0);
+    for _ in 0..iteration_count {
+        if rng.random_bool(0.01) {
+            pv.optimize();
+        }
+        if rng.random_bool(0.5) {
+            let n = rng.random_range(0..max_elem);
+            pv.push(n);
+            control.push(n);
+        }
+        if rng.random_bool(0.05) {
+            let old = rng.random_range(0..max_elem);
+            let new = rng.random_range(0..max_elem * 2);
+            let mut replaced = 0;
+            for value in control.iter_mut() {
+                if *value == old {
+                    *value = new;
+                    replaced += 1;
+                }
+            }
+            assert_eq!(pv.replace_all(&old, new), replaced);
+            assert_eq!(pv.count_of(&old), if old == new { replaced } else { 0 });
+        }
+        assert_eq!(pv.len(), control.len());
+    }
+    for (i, value) in pv.iter().enumerate() {
+        assert_eq!(*value, control[i]);
+    }
+    for entry in pv.iter_palette_entries() {
+        let control_count = control.iter().filter(|value| **value == entry.value).count();
+        assert_eq!(count_to_usize(entry.count), control_count);
+    }
+    while let Some(value) = pv.pop() {
+        assert_eq!(value, control.pop().unwrap());
+    }
+}
+
+fn test_palette_vec_map<P, B>(amount_unique_values: usize, modulo: u32, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer + Clone,
+{
+    let mut pv: PaletteVec<u32, P, B> = PaletteVec::new();
+    for i in 0..iteration_count {
+        pv.push(((i * 13) % amount_unique_values) as u32);
+    }
+    // Remove some values to create holes in the palette
+    for i in 0..iteration_count {
+        if ((i * 13) % amount_unique_values) % 5 == 1 {
+            pv.set(i, &0);
+        }
+    }
+
+    let mut calls = 0;
+    let mapped: PaletteVec<u32, P, B> = pv.map(|value| {
+        calls += 1;
+        value % modulo
+    });
+    assert_eq!(calls, pv.unique_values());
+    assert_eq!(mapped.len(), pv.len());
+    for (value, mapped_value) in pv.iter().zip(mapped.iter()) {
+        assert_eq!(value % modulo, *mapped_value);
+    }
+    for entry in mapped.iter_palette_entries() {
+        let control_count = pv.iter().filter(|value| **value % modulo == entry.value).count();
+        assert_eq!(count_to_usize(entry.count), control_count);
+    }
+
+    // Mapping into another element type and palette
+    let mapped: PaletteVec<bool, VecPalette<bool>, B> =
+        pv.map(|value| value.is_multiple_of(2));
+    for (value, mapped_value) in pv.iter().zip(mapped.iter()) {
+        assert_eq!(value.is_multiple_of(2), *mapped_value);
+    }
+    let mut mapped = mapped;
+    while let Some(mapped_value) = mapped.pop() {
+        assert_eq!(pv.pop().unwrap().is_multiple_of(2), mapped_value);
+    }
+}
+
+fn test_palette_vec_iter_double_ended<P, B>(amount_unique_values: usize, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    let mut pv: PaletteVec<u32, P, B> = PaletteVec::new();
+    let mut control = Vec::new();
+    for i in 0..iteration_count {
+        let value = ((i * 13) % amount_unique_values) as u32;
+        pv.push(value);
+        control.push(value);
+    }
+    assert_eq!(pv.iter().len(), control.len());
+    assert!(pv.iter().rev().eq(control.iter().rev()));
+    for step in [0, 3, 64, 500] {
+        assert!(pv.iter().skip(step).eq(control.iter().skip(step)));
+        assert!(pv.iter().step_by(step + 1).eq(control.iter().step_by(step + 1)));
+        assert_eq!(pv.iter().nth_back(step), control.iter().nth_back(step));
+    }
+    for start in (0..iteration_count).step_by(97) {
+        for end in [start, start + 1, (start + 333).min(iteration_count), iteration_count] {
+            assert_eq!(pv.iter_range(start..end).len(), end - start);
+            assert!(pv.iter_range(start..end).eq(control[start..end].iter()));
+            assert!(pv
+                .iter_range(start..end)
+                .rev()
+                .eq(control[start..end].iter().rev()));
+        }
+    }
+    assert!(pv.iter_range(..).eq(control.iter()));
+    assert!(pv.iter_range(..=0).eq(control[..=0].iter()));
+}
+
+fn test_palette_vec_iter_runs<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let amount_unique_values = rng.random_range(1..40);
+    let mut pv: PaletteVec<u32, P, B> = PaletteVec::new();
+    let mut control = Vec::new();
+    while control.len() < iteration_count {
+        let value = rng.random_range(0..amount_unique_values);
+        for _ in 0..rng.random_range(1..70) {
+            pv.push(value);
+            control.push(value);
+        }
+    }
+    let control_runs = control
+        .chunk_by(|a, b| a == b)
+        .map(|run| (&run[0], run.len()))
+        .collect::<Vec<_>>();
+    assert!(pv.iter_runs().eq(control_runs.iter().copied()));
+    assert!(pv.iter_runs().rev().eq(control_runs.iter().rev().copied()));
+
+    // Alternate between both ends
+    let mut runs = pv.iter_runs();
+    let mut control_iter = control_runs.iter().copied();
+    loop {
+        let (run, control_run) = if rng.random_bool(0.5) {
+            (runs.next_back(), control_iter.next_back())
+        } else {
+            (runs.next(), control_iter.next())
+        };
+        assert_eq!(run, control_run);
+        let (lower, upper) = runs.size_hint();
+        assert!(lower <= control_iter.len() && control_iter.len() <= upper.unwrap());
+        if run.is_none() {
+            break;
+        }
+    }
+
+    let filled: PaletteVec<u32, P, B> = PaletteVec::filled(7, iteration_count);
+    assert_eq!(filled.iter_runs().size_hint(), (1, Some(1)));
+    assert_eq!(filled.iter_runs().collect::<Vec<_>>(), [(&7, iteration_count)]);
+    assert_eq!(filled.iter_runs().next_back(), Some((&7, iteration_count)));
+    assert_eq!(PaletteVec::<u32, P, B>::new().iter_runs().next(), None);
+}
+
+fn test_palette_vec_slice<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let amount_unique_values = rng.random_range(1..40);
+    let mut pv: PaletteVec<u32, P, B> = PaletteVec::new();
+    let mut control = Vec::new();
+    for _ in 0..iteration_count {
+        let value = rng.random_range(0..amount_unique_values);
+        pv.push(value);
+        control.push(value);
+    }
+
+    for _ in 0..100 {
+        let start = rng.random_range(0..=control.len());
+        let end = rng.random_range(start..=control.len());
+        let slice = pv.slice(start..end);
+        let control_slice = &control[start..end];
+        assert_eq!(slice.len(), control_slice.len());
+        assert_eq!(slice.is_empty(), control_slice.is_empty());
+        for i in 0..control_slice.len() + 3 {
+            assert_eq!(slice.get(i), control_slice.get(i));
+        }
+        if let Some(first) = control_slice.first() {
+            assert_eq!(&slice[0], first);
+        }
+        assert!(slice.iter().eq(control_slice.iter()));
+        let control_runs = control_slice
+            .chunk_by(|a, b| a == b)
+            .map(|run| (&run[0], run.len()))
+            .collect::<Vec<_>>();
+        assert!(slice.iter_runs().eq(control_runs.iter().copied()));
+        for value in 0..amount_unique_values + 2 {
+            assert_eq!(
+                slice.count_of(&value),
+                control_slice.iter().filter(|v| **v == value).count()
+            );
+        }
+        let sub = slice.slice(slice.len() / 3..slice.len() / 2);
+        assert!(sub.iter().eq(control_slice[control_slice.len() / 3..control_slice.len() / 2].iter()));
+
+        let copy = slice.to_palette_vec();
+        assert!(copy.iter().eq(control_slice.iter()));
+        let mut control_unique = control_slice.to_vec();
+        control_unique.sort();
+        control_unique.dedup();
+        assert_eq!(copy.unique_values(), control_unique.len());
+    }
+
+    for _ in 0..100 {
+        let start = rng.random_range(0..=control.len());
+        let end = rng.random_range(start..=control.len());
+        let mut slice = pv.slice_mut(start..end);
+        if rng.random_bool(0.3) {
+            let value = rng.random_range(0..amount_unique_values * 2);
+            slice.fill(&value);
+            control[start..end].fill(value);
+        } else {
+            for _ in 0..(end - start).min(20) {
+                let offset = rng.random_range(0..end - start);
+                let value = rng.random_range(0..amount_unique_values * 2);
+                slice.set(offset, &value);
+                control[start + offset] = value;
+            }
+        }
+        assert!(slice.iter().eq(control[start..end].iter()));
+        assert_eq!(slice.as_slice().len(), end - start);
+    }
+    assert!(pv.iter().eq(control.iter()));
+    let mut control_unique = control.clone();
+    control_unique.sort();
+    control_unique.dedup();
+    assert_eq!(pv.unique_values(), control_unique.len());
+}
+
+fn test_palette_vec_into_iter_drain<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let amount_unique_values = rng.random_range(1..40);
+    let mut pv: PaletteVec<u32, P, B> = PaletteVec::new();
+    let mut control = Vec::new();
+    for _ in 0..iteration_count {
+        let value = rng.random_range(0..amount_unique_values);
+        pv.push(value);
+        control.push(value);
+    }
+
+    // Owning iteration from both ends
+    let copy: PaletteVec<u32, P, B> = pv.iter().cloned().collect();
+    let mut into_iter = copy.into_iter();
+    let mut control_iter = control.clone().into_iter();
+    assert_eq!(into_iter.len(), control_iter.len());
+    loop {
+        let (value, control_value) = if rng.random_bool(0.3) {
+            (into_iter.next_back(), control_iter.next_back())
+        } else {
+            (into_iter.next(), control_iter.next())
+        };
+        assert_eq!(value, control_value);
+        assert_eq!(into_iter.len(), control_iter.len());
+        if value.is_none() {
+            break;
+        }
+    }
+
+    while !control.is_empty() {
+        let start = rng.random_range(0..=control.len());
+        let end = rng.random_range(start..=(start + 200).min(control.len()));
+        let consume = rng.random_range(0..=end - start);
+        let mut drain = pv.drain(start..end);
+        let mut control_drain = control.drain(start..end);
+        assert_eq!(drain.len(), control_drain.len());
+        for _ in 0..consume {
+            if rng.random_bool(0.5) {
+                assert_eq!(drain.next(), control_drain.next());
+            } else {
+                assert_eq!(drain.next_back(), control_drain.next_back());
+            }
+        }
+        drop(drain);
+        drop(control_drain);
+        assert_eq!(pv.len(), control.len());
+        assert!(pv.iter().eq(control.iter()));
+        for value in 0..amount_unique_values {
+            assert_eq!(
+                pv.count_of(&value),
+                control.iter().filter(|v| **v == value).count()
+            );
+        }
+    }
+    assert_eq!(pv.unique_values(), 0);
+
+    // Leaking the drain still leaves a consistent vector behind
+    let values = (0..20).map(|i| i % 3).collect::<Vec<u32>>();
+    let mut pv: PaletteVec<u32, P, B> = values.as_slice().into();
+    std::mem::forget(pv.drain(0..5));
+    assert!(pv.iter().eq(values[5..].iter()));
+    assert_eq!(pv.count_of(&0), 5);
+    assert_eq!(pv.check_invariants(), Ok(()));
+    pv.clear();
+
+    pv.push(3);
+    assert_eq!(pv.into_iter().collect::<Vec<_>>(), [3]);
+}
+
+/// Counts its clones, equality and hashing only look at the id.
+#[derive(Debug)]
+struct CloneCounted {
+    id: u32,
+    clones: std::rc::Rc<std::cell::Cell<usize>>,
+}
+
+impl Clone for CloneCounted {
+    fn clone(&self) -> Self {
+        self.clones.set(self.clones.get() + 1);
+        Self {
+            id: self.id,
+            clones: self.clones.clone(),
+        }
+    }
+}
+
+impl PartialEq for CloneCounted {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for CloneCounted {}
+
+impl std::hash::Hash for CloneCounted {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+fn test_palette_vec_into_iter_moves_last<P, B>(amount_unique_values: usize, iteration_count: usize)
+where
+    P: Palette<CloneCounted>,
+    B: IndexBuffer,
+{
+    let clones = std::rc::Rc::new(std::cell::Cell::new(0));
+    let value = |id: usize| CloneCounted {
+        id: (id % amount_unique_values) as u32,
+        clones: clones.clone(),
+    };
+
+    let mut pv: PaletteVec<CloneCounted, P, B> = PaletteVec::new();
+    for i in 0..iteration_count {
+        pv.push(value(i));
+    }
+    clones.set(0);
+    assert_eq!(pv.into_iter().count(), iteration_count);
+    assert_eq!(clones.get(), iteration_count - amount_unique_values);
+
+    let mut pv: PaletteVec<CloneCounted, P, B> = PaletteVec::new();
+    for i in 0..iteration_count {
+        pv.push(value(i));
+    }
+    clones.set(0);
+    assert_eq!(pv.drain(..).count(), iteration_count);
+    assert_eq!(clones.get(), iteration_count - amount_unique_values);
+    assert!(pv.is_empty());
+    assert_eq!(pv.unique_values(), 0);
+
+    for i in 0..iteration_count {
+        pv.push(value(i));
+    }
+    clones.set(0);
+    assert_eq!(pv.into_vec().len(), iteration_count);
+    assert_eq!(clones.get(), iteration_count - amount_unique_values);
+}
+
+fn test_palette_vec_eq_hash_ord<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    fn hash_of<H: std::hash::Hash>(value: &H) -> u64 {
+        use std::hash::{BuildHasher, BuildHasherDefault};
+        BuildHasherDefault::<rustc_hash::FxHasher>::default().hash_one(value)
+    }
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let amount_unique_values = rng.random_range(1..40);
+    let mut control = Vec::new();
+    for _ in 0..iteration_count {
+        control.push(rng.random_range(0..amount_unique_values));
+    }
+
+    // Same sequence, different palette order
+    let a: PaletteVec<u32, P, B> = control.iter().copied().collect();
+    let mut b: PaletteVec<u32, P, B> = control.iter().rev().copied().collect();
+    b.reverse();
+    assert!(a == b);
+    assert_eq!(hash_of(&a), hash_of(&b));
+    assert_eq!(a.cmp(&b), std::cmp::Ordering::Equal);
+
+    // Different backend
+    let c: PaletteVec<u32, VecPalette<u32>, B> = control.iter().copied().collect();
+    assert!(a == c);
+    assert!(c == b);
+
+    // Same palette, but a bigger index size
+    let mut d: PaletteVec<u32, P, B> = control.iter().copied().collect();
+    d.push(amount_unique_values + 1000);
+    for i in 0..300 {
+        d.push(amount_unique_values + i);
+    }
+    d.truncate(control.len());
+    assert!(a == d);
+    assert_eq!(hash_of(&a), hash_of(&d));
+
+    // Single changes are noticed
+    for _ in 0..20 {
+        let offset = rng.random_range(0..control.len());
+        let mut changed_control = control.clone();
+        changed_control[offset] = rng.random_range(0..amount_unique_values + 1);
+        let mut changed: PaletteVec<u32, P, B> = control.iter().copied().collect();
+        changed.set(offset, &changed_control[offset]);
+        assert_eq!(a == changed, control == changed_control);
+        assert_eq!(b == changed, control == changed_control);
+        assert_eq!(c == changed, control == changed_control);
+        assert_eq!(a.cmp(&changed), control.cmp(&changed_control));
+        assert_eq!(
+            c.partial_cmp(&changed),
+            control.partial_cmp(&changed_control)
+        );
+    }
+
+    let mut shorter: PaletteVec<u32, P, B> = control.iter().copied().collect();
+    shorter.pop();
+    assert!(a != shorter);
+    assert!(shorter < a);
+    assert!(PaletteVec::<u32, P, B>::new() == PaletteVec::<u32, VecPalette<u32>, B>::new());
+}
+
+fn test_palette_vec_vec_conversions<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let amount_unique_values = rng.random_range(1..300);
+    let mut control = Vec::new();
+    for _ in 0..rng.random_range(0..iteration_count) {
+        control.push(rng.random_range(0..amount_unique_values));
+    }
+
+    let from_vec: PaletteVec<u32, P, B> = control.clone().into();
+    let from_slice: PaletteVec<u32, P, B> = control.as_slice().into();
+    assert_eq!(from_vec.len(), control.len());
+    assert!(from_vec.iter().eq(control.iter()));
+    assert!(from_slice.iter().eq(control.iter()));
+    let mut control_unique = control.clone();
+    control_unique.sort();
+    control_unique.dedup();
+    assert_eq!(from_vec.unique_values(), control_unique.len());
+
+    assert_eq!(from_vec.to_vec(), control);
+    assert_eq!(from_slice.into_vec(), control);
+    assert_eq!(Vec::from(from_vec), control);
+
+    // Garbage behind the last index must not show up
+    let mut pv: PaletteVec<u32, P, B> = control.as_slice().into();
+    for _ in 0..rng.random_range(0..50) {
+        pv.push(rng.random_range(0..amount_unique_values * 2));
+    }
+    pv.truncate(control.len());
+    assert_eq!(pv.to_vec(), control);
+    assert_eq!(pv.into_vec(), control);
+}
+
+fn test_palette_vec_sort<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let amount_unique_values = rng.random_range(1..300);
+    let mut control = Vec::new();
+    for _ in 0..rng.random_range(0..iteration_count) {
+        control.push(rng.random_range(0..amount_unique_values));
+    }
+
+    let mut pv: PaletteVec<u32, P, B> = control.as_slice().into();
+    pv.sort();
+    control.sort();
+    assert!(pv.iter().eq(control.iter()));
+
+    pv.sort_by(|a, b| b.cmp(a));
+    control.sort_by(|a, b| b.cmp(a));
+    assert!(pv.iter().eq(control.iter()));
+
+    pv.sort_by_key(|value| value % 7);
+    control.sort_by_key(|value| value % 7);
+    for (value, control_value) in pv.iter().zip(control.iter()) {
+        assert_eq!(value % 7, control_value % 7);
+    }
+    assert!(pv.iter().is_sorted_by_key(|value| *value % 7));
+    for value in 0..amount_unique_values {
+        assert_eq!(
+            pv.count_of(&value),
+            control.iter().filter(|v| **v == value).count()
+        );
+    }
+    pv.push(amount_unique_values);
+    assert_eq!(pv.pop(), Some(amount_unique_values));
+}
+
+fn test_palette_vec_packed_longs<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    use crate::minecraft::PackedLongsError;
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let amount_unique_values = rng.random_range(1..300);
+    let mut control = Vec::new();
+    for _ in 0..rng.random_range(0..iteration_count) {
+        control.push(rng.random_range(0..amount_unique_values));
+    }
+    let mut pv: PaletteVec<u32, P, B> = control.as_slice().into();
+    // Leave holes in the palette and garbage behind the last index
+    for _ in 0..rng.random_range(0..20) {
+        pv.push(amount_unique_values + rng.random_range(0..100));
+    }
+    pv.truncate(control.len());
+
+    for min_bits in [0, 1, 4, 9] {
+        let packed = pv.to_packed_longs(min_bits);
+        let mut control_unique = control.clone();
+        control_unique.sort();
+        control_unique.dedup();
+        assert_eq!(packed.palette.len(), control_unique.len());
+        if control_unique.len() <= 1 {
+            assert_eq!(packed.bits, 0);
+            assert!(packed.longs.is_empty());
+        } else {
+            let needed = (usize::BITS - (control_unique.len() - 1).leading_zeros()) as usize;
+            assert_eq!(packed.bits, needed.max(min_bits));
+            let per_long = 64 / packed.bits;
+            let mask = u64::MAX >> (64 - packed.bits);
+            assert_eq!(packed.longs.len(), control.len().div_ceil(per_long));
+            for (i, value) in control.iter().enumerate() {
+                let index = (packed.longs[i / per_long] >> (i % per_long * packed.bits)) & mask;
+                assert_eq!(packed.palette[index as usize], *value);
+            }
+            // Unused bits must be zero
+            for i in control.len()..packed.longs.len() * per_long {
+                let index = (packed.longs[i / per_long] >> (i % per_long * packed.bits)) & mask;
+                assert_eq!(index, 0);
+            }
+            for long in packed.longs.iter() {
+                assert_eq!(long.checked_shr((per_long * packed.bits) as u32).unwrap_or(0), 0);
+            }
+        }
+
+        let imported: PaletteVec<u32, P, B> =
+            PaletteVec::from_packed_longs(packed.bits, control.len(), &packed.palette, &packed.longs)
+                .unwrap();
+        assert!(imported.iter().eq(control.iter()));
+        assert_eq!(imported.unique_values(), control_unique.len());
+    }
+
+    // Hand packed: 4 bits minimum, [1, 0, 1]
+    let imported: PaletteVec<u32, P, B> =
+        PaletteVec::from_packed_longs(4, 3, &[7, 8], &[0x101]).unwrap();
+    assert_eq!(imported.to_vec(), [8, 7, 8]);
+    assert_eq!(imported.to_packed_longs(4).longs, [0x101]);
+    // Duplicate and unused palette values
+    let imported: PaletteVec<u32, P, B> =
+        PaletteVec::from_packed_longs(2, 4, &[5, 6, 5, 9], &[0b10_00_10_00]).unwrap();
+    assert_eq!(imported.to_vec(), [5, 5, 5, 5]);
+    assert_eq!(imported.unique_values(), 1);
+    let single: PaletteVec<u32, P, B> = PaletteVec::from_packed_longs(0, 4096, &[3], &[]).unwrap();
+    assert_eq!(single.len(), 4096);
+    assert_eq!(single.to_packed_longs(4).bits, 0);
+    let empty: PaletteVec<u32, P, B> = PaletteVec::new();
+    let packed = empty.to_packed_longs(4);
+    let imported: PaletteVec<u32, P, B> =
+        PaletteVec::from_packed_longs(packed.bits, 0, &packed.palette, &packed.longs).unwrap();
+    assert!(imported.is_empty());
+
+    assert_eq!(
+        PaletteVec::<u32, P, B>::from_packed_longs(65, 1, &[1], &[0]).err(),
+        Some(PackedLongsError::BitsOutOfRange(65))
+    );
+    assert_eq!(
+        PaletteVec::<u32, P, B>::from_packed_longs(4, 17, &[1, 2], &[0]).err(),
+        Some(PackedLongsError::WrongLongCount {
+            expected: 2,
+            found: 1
+        })
+    );
+    assert_eq!(
+        PaletteVec::<u32, P, B>::from_packed_longs(4, 3, &[1, 2], &[0x200]).err(),
+        Some(PackedLongsError::IndexOutOfPalette {
+            offset: 2,
+            index: 2
+        })
+    );
+    assert_eq!(
+        PaletteVec::<u32, P, B>::from_packed_longs(0, 3, &[1, 2], &[]).err(),
+        Some(PackedLongsError::SingleValuePalette(2))
+    );
+    assert_eq!(
+        PaletteVec::<u32, P, B>::from_packed_longs(0, 3, &[], &[]).err(),
+        Some(PackedLongsError::SingleValuePalette(0))
+    );
+}
+
+fn test_palette_vec_varint_data<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    use crate::schematic::VarintDataError;
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let amount_unique_values = rng.random_range(1..1000);
+    let mut control = Vec::new();
+    for _ in 0..rng.random_range(0..iteration_count) {
+        control.push(rng.random_range(0..amount_unique_values));
+    }
+    let mut pv: PaletteVec<u32, P, B> = control.as_slice().into();
+    // Leave holes in the palette
+    for _ in 0..rng.random_range(0..20) {
+        pv.push(amount_unique_values + rng.random_range(0..100));
+    }
+    pv.truncate(control.len());
+
+    let exported = pv.to_varint_data(|value| format!("block:{}", value));
+    assert_eq!(exported.palette.len(), pv.unique_values());
+    for (id, (_, palette_id)) in exported.palette.iter().enumerate() {
+        assert_eq!(*palette_id as usize, id);
+    }
+    let imported: PaletteVec<u32, P, B> = PaletteVec::from_varint_data(
+        &exported.palette,
+        &exported.data,
+        control.len(),
+        |key| key["block:".len()..].parse().unwrap(),
+    )
+    .unwrap();
+    assert!(imported.iter().eq(control.iter()));
+    assert_eq!(imported.unique_values(), pv.unique_values());
+
+    // Corrupted data must never panic
+    let mut data = exported.data.clone();
+    for _ in 0..rng.random_range(0..8) {
+        if data.is_empty() {
+            break;
+        }
+        let offset = rng.random_range(0..data.len());
+        data[offset] = rng.random();
+    }
+    let _ = PaletteVec::<u32, P, B>::from_varint_data(
+        &exported.palette,
+        &data,
+        control.len(),
+        |key| key["block:".len()..].parse().unwrap(),
+    );
+
+    // Hand encoded: ids 300 and 1, 300 takes two bytes
+    let palette = [(7, 300), (8, 1)];
+    let imported: PaletteVec<u32, P, B> =
+        PaletteVec::from_varint_data(&palette, &[0xAC, 0x02, 0x01, 0xAC, 0x02], 3, |key| *key)
+            .unwrap();
+    assert_eq!(imported.to_vec(), [7, 8, 7]);
+    // Keys mapping to the same value are merged
+    let imported: PaletteVec<u32, P, B> =
+        PaletteVec::from_varint_data(&palette, &[0xAC, 0x02, 0x01], 2, |_| 5).unwrap();
+    assert_eq!(imported.to_vec(), [5, 5]);
+    assert_eq!(imported.unique_values(), 1);
+    let empty: PaletteVec<u32, P, B> =
+        PaletteVec::from_varint_data::<u32>(&[], &[], 0, |key| *key).unwrap();
+    assert!(empty.is_empty());
+    let exported = empty.to_varint_data(|value| *value);
+    assert!(exported.palette.is_empty() && exported.data.is_empty());
+
+    assert_eq!(
+        PaletteVec::<u32, P, B>::from_varint_data(&palette, &[0x01, 0xAC], 2, |key| *key).err(),
+        Some(VarintDataError::Truncated { offset: 1 })
+    );
+    assert_eq!(
+        PaletteVec::<u32, P, B>::from_varint_data(
+            &palette,
+            &[0xFF, 0xFF, 0xFF, 0xFF, 0x1F],
+            1,
+            |key| *key
+        )
+        .err(),
+        Some(VarintDataError::Overflow { offset: 0 })
+    );
+    assert_eq!(
+        PaletteVec::<u32, P, B>::from_varint_data(&palette, &[0x01, 0x02], 2, |key| *key).err(),
+        Some(VarintDataError::UnknownId { offset: 1, id: 2 })
+    );
+    assert_eq!(
+        PaletteVec::<u32, P, B>::from_varint_data(&[(1, 0), (2, 0)], &[0x00], 1, |key| *key)
+            .err(),
+        Some(VarintDataError::DuplicateId(0))
+    );
+    assert_eq!(
+        PaletteVec::<u32, P, B>::from_varint_data(&palette, &[0x01, 0x01], 3, |key| *key).err(),
+        Some(VarintDataError::WrongLength {
+            expected: 3,
+            found: 2
+        })
+    );
+}
+
+#[cfg(feature = "serde")]
+fn test_palette_vec_canonical_serde<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    use crate::{
+        index_buffer::{aligned::AlignedIndexBuffer, fast::FastIndexBuffer},
+        palette::hybrid::HybridPalette,
+    };
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct Wrapper<T: Eq + std::hash::Hash + Clone, P: Palette<T>, B: IndexBuffer> {
+        #[serde(with = "crate::canonical")]
+        #[serde(bound(serialize = "T: serde::Serialize"))]
+        #[serde(bound(deserialize = "T: serde::Deserialize<'de>"))]
+        data: PaletteVec<T, P, B>,
+    }
+    type Fast = Wrapper<u32, VecPalette<u32>, FastIndexBuffer>;
+    type Aligned = Wrapper<u32, HybridPalette<4, u32>, AlignedIndexBuffer>;
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let amount_unique_values = rng.random_range(1..300);
+    let mut control = Vec::new();
+    let target_len = rng.random_range(0..iteration_count);
+    while control.len() < target_len {
+        let value = rng.random_range(0..amount_unique_values);
+        for _ in 0..rng.random_range(1..50) {
+            control.push(value);
+        }
+    }
+    let mut pv: PaletteVec<u32, P, B> = control.as_slice().into();
+    // Leave holes in the palette and garbage behind the last index
+    for _ in 0..rng.random_range(0..20) {
+        pv.push(amount_unique_values + rng.random_range(0..100));
+    }
+    pv.truncate(control.len());
+    let original = Wrapper { data: pv };
+
+    let json = serde_json::to_string(&original).unwrap();
+    let fast: Fast = serde_json::from_str(&json).unwrap();
+    let aligned: Aligned = serde_json::from_str(&json).unwrap();
+    assert!(fast.data.iter().eq(control.iter()));
+    assert!(aligned.data.iter().eq(control.iter()));
+    assert_eq!(fast.data.unique_values(), original.data.unique_values());
+    // The same data serializes the same, no matter the backend
+    assert_eq!(serde_json::to_string(&fast).unwrap(), json);
+    assert_eq!(serde_json::to_string(&aligned).unwrap(), json);
+
+    let binary = bincode::serialize(&original).unwrap();
+    let fast: Fast = bincode::deserialize(&binary).unwrap();
+    let aligned: Aligned = bincode::deserialize(&binary).unwrap();
+    assert!(fast.data.iter().eq(control.iter()));
+    assert!(aligned.data.iter().eq(control.iter()));
+    assert_eq!(bincode::serialize(&fast).unwrap(), binary);
+    assert_eq!(bincode::serialize(&aligned).unwrap(), binary);
+
+    // Hand written runs, with a duplicate and an unused palette value
+    let parsed: Wrapper<u32, P, B> = serde_json::from_str(
+        r#"{"data":{"palette":[7,8,7,9],"runs":[[0,3],[1,2],[2,1],[0,0]]}}"#,
+    )
+    .unwrap();
+    assert_eq!(parsed.data.to_vec(), [7, 7, 7, 8, 8, 7]);
+    assert_eq!(parsed.data.unique_values(), 2);
+    let parsed: Wrapper<u32, P, B> =
+        serde_json::from_str(r#"{"data":{"palette":[],"runs":[]}}"#).unwrap();
+    assert!(parsed.data.is_empty());
+    let empty = Wrapper {
+        data: PaletteVec::<u32, P, B>::new(),
+    };
+    let parsed: Wrapper<u32, P, B> =
+        bincode::deserialize(&bincode::serialize(&empty).unwrap()).unwrap();
+    assert!(parsed.data.is_empty());
+
+    assert!(serde_json::from_str::<Wrapper<u32, P, B>>(
+        r#"{"data":{"palette":[1],"runs":[[1,3]]}}"#
+    )
+    .is_err());
+    assert!(serde_json::from_str::<Wrapper<u32, P, B>>(&format!(
+        r#"{{"data":{{"palette":[1],"runs":[[0,{}],[0,{}]]}}}}"#,
+        usize::MAX,
+        usize::MAX
+    ))
+    .is_err());
+    let mut corrupted = binary.clone();
+    for _ in 0..rng.random_range(1..8) {
+        let offset = rng.random_range(0..corrupted.len());
+        corrupted[offset] = rng.random();
+    }
+    // Must not panic, but may happen to still be valid
+    let _ = bincode::deserialize::<Wrapper<u32, P, B>>(&corrupted);
+}
+
+fn test_palette_vec_check_invariants<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    use crate::InvariantError;
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut pv: PaletteVec<u32, P, B> = PaletteVec::new();
+    assert_eq!(pv.check_invariants(), Ok(()));
+    let max_elem = rng.random_range(1..300);
+    for _ in 0..iteration_count {
+        match rng.random_range(0..10) {
+            0..=3 => pv.push(rng.random_range(0..max_elem)),
+            4 if !pv.is_empty() => {
+                let offset = rng.random_range(0..pv.len());
+                pv.set(offset, &rng.random_range(0..max_elem));
+            }
+            5 if !pv.is_empty() => {
+                let offset = rng.random_range(0..pv.len());
+                pv.remove(offset);
+            }
+            6 => {
+                let start = rng.random_range(0..=pv.len());
+                let end = rng.random_range(start..=pv.len());
+                pv.fill_range(start..end, &rng.random_range(0..max_elem));
+            }
+            7 => pv.truncate(rng.random_range(0..=pv.len())),
+            8 => pv.optimize(),
+            _ => {
+                pv.pop();
+            }
+        }
+        assert_eq!(pv.check_invariants(), Ok(()));
+    }
+
+    if pv.is_empty() {
+        pv.push(1);
+    }
+    if pv.unique_values() == 1 {
+        pv.push(2);
+    }
+    let values = pv.to_vec();
+    // Counts that don't match the buffer
+    let mut corrupted: PaletteVec<u32, P, B> = values.as_slice().into();
+    corrupted.iter_palette_entries_mut().next().unwrap().count += 1;
+    assert!(matches!(
+        corrupted.check_invariants(),
+        Err(InvariantError::CountMismatch { .. })
+    ));
+    let mut corrupted: PaletteVec<u32, P, B> = values.as_slice().into();
+    corrupted.iter_palette_entries_mut().next().unwrap().count = 0;
+    assert!(matches!(
+        corrupted.check_invariants(),
+        Err(InvariantError::ZeroCount { .. })
+    ));
+    // An index without palette entry
+    let mut corrupted: PaletteVec<u32, P, B> = values.as_slice().into();
+    let missing = (0..corrupted.palette.index_bound())
+        .find(|index| corrupted.palette.get_by_index(*index).is_none())
+        .unwrap_or(corrupted.palette.index_bound());
+    if missing < 1 << corrupted.buffer.bits_per_index() {
+        corrupted.buffer.set_index(0, missing);
+        assert_eq!(
+            corrupted.check_invariants(),
+            Err(InvariantError::MissingEntry {
+                offset: 0,
+                index: missing
+            })
+        );
+    }
+    // An index without a count
+    let mut corrupted: PaletteVec<u32, P, B> = values.as_slice().into();
+    corrupted.buffer.push_index(0);
+    assert!(matches!(
+        corrupted.check_invariants(),
+        Err(InvariantError::CountMismatch { .. })
+    ));
+}
+
+#[cfg(feature = "serde")]
+fn test_palette_vec_validated_serde<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32> + serde::Serialize + serde::de::DeserializeOwned,
+    B: IndexBuffer + serde::Serialize + serde::de::DeserializeOwned,
+{
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let amount_unique_values = rng.random_range(2..300);
+    let mut pv: PaletteVec<u32, P, B> = PaletteVec::new();
+    for _ in 0..rng.random_range(1..iteration_count) {
+        pv.push(rng.random_range(0..amount_unique_values));
+    }
+    pv.push(amount_unique_values);
+
+    let json = serde_json::to_value(&pv).unwrap();
+    let parsed: PaletteVec<u32, P, B> = serde_json::from_value(json.clone()).unwrap();
+    assert!(parsed == pv);
+
+    let tampered = |path: [&str; 2], value: serde_json::Value| {
+        let mut json = json.clone();
+        json[path[0]][path[1]] = value;
+        serde_json::from_value::<PaletteVec<u32, P, B>>(json).is_err()
+    };
+    assert!(tampered(["buffer", "len"], (pv.len() + 1).into()));
+    assert!(tampered(["buffer", "len"], (pv.len() - 1).into()));
+    assert!(tampered(["buffer", "len"], usize::MAX.into()));
+    assert!(tampered(["buffer", "mask"], 12345.into()));
+    assert!(tampered(["buffer", "indices_per_u64"], 0.into()));
+    assert!(tampered(["buffer", "index_size"], 65.into()));
+    assert!(tampered(["buffer", "storage"], serde_json::json!([])));
+    let mut storage = json["buffer"]["storage"].as_array().unwrap().clone();
+    storage.push(0.into());
+    assert!(tampered(["buffer", "storage"], storage.into()));
+    assert!(tampered(["palette", "index_size"], 0.into()));
+    assert!(tampered(["palette", "index_size"], 200.into()));
+    assert!(tampered(["palette", "real_entries"], 1.into()));
+
+    // Corrupted binary data is either rejected or fully usable
+    let binary = bincode::serialize(&pv).unwrap();
+    for _ in 0..32 {
+        let mut corrupted = binary.clone();
+        for _ in 0..rng.random_range(1..4) {
+            let offset = rng.random_range(0..corrupted.len());
+            corrupted[offset] = rng.random();
+        }
+        if let Ok(mut parsed) = bincode::deserialize::<PaletteVec<u32, P, B>>(&corrupted) {
+            assert_eq!(parsed.check_invariants(), Ok(()));
+            assert_eq!(parsed.iter().count(), parsed.len());
+            parsed.push(amount_unique_values + 1);
+            parsed.optimize();
+            while parsed.pop().is_some() {}
+        }
+    }
+}
+
+#[cfg(feature = "bitcode")]
+fn test_palette_vec_validated_bitcode<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32> + bitcode::Encode + bitcode::DecodeOwned,
+    B: IndexBuffer + bitcode::Encode + bitcode::DecodeOwned,
+{
+    use crate::{DecodeError, InvariantError};
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let amount_unique_values = rng.random_range(2..300);
+    let mut pv: PaletteVec<u32, P, B> = PaletteVec::new();
+    for _ in 0..rng.random_range(1..iteration_count) {
+        pv.push(rng.random_range(0..amount_unique_values));
+    }
+    pv.push(amount_unique_values);
+
+    let bytes = bitcode::encode(&pv);
+    let parsed = PaletteVec::<u32, P, B>::decode_bitcode(&bytes).unwrap();
+    assert!(parsed == pv);
+    assert!(matches!(
+        PaletteVec::<u32, P, B>::decode_bitcode(&bytes[..bytes.len() - 1]),
+        Err(DecodeError::Bitcode(_))
+    ));
+
+    // Valid bitcode, but the counts don't match the indices anymore
+    let index = pv.palette.get_by_value(&amount_unique_values).unwrap().1;
+    pv.palette.get_mut_by_index(index).unwrap().count += 1;
+    assert!(matches!(
+        PaletteVec::<u32, P, B>::decode_bitcode(&bitcode::encode(&pv)),
+        Err(DecodeError::Invariant(InvariantError::CountMismatch { .. }))
+    ));
+
+    // Corrupted bytes are either rejected or fully usable
+    for _ in 0..32 {
+        let mut corrupted = bytes.clone();
+        for _ in 0..rng.random_range(1..4) {
+            let offset = rng.random_range(0..corrupted.len());
+            corrupted[offset] = rng.random();
+        }
+        if let Ok(mut parsed) = PaletteVec::<u32, P, B>::decode_bitcode(&corrupted) {
+            assert_eq!(parsed.iter().count(), parsed.len());
+            parsed.push(amount_unique_values + 1);
+            parsed.optimize();
+            while parsed.pop().is_some() {}
+        }
+    }
+}
+
+fn test_palette_vec_native_format<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    use std::io::ErrorKind;
+
+    use crate::{
+        index_buffer::fast::FastIndexBuffer,
+        native::{LeBytes, MAGIC, VERSION},
+    };
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let amount_unique_values = rng.random_range(1..300);
+    let mut control = Vec::new();
+    for _ in 0..rng.random_range(0..iteration_count) {
+        control.push(rng.random_range(0..amount_unique_values));
+    }
+    let mut pv: PaletteVec<u32, P, B> = control.as_slice().into();
+    // Leave holes in the palette and garbage behind the last index
+    for _ in 0..rng.random_range(0..20) {
+        pv.push(amount_unique_values + rng.random_range(0..100));
+    }
+    pv.truncate(control.len());
+
+    let mut bytes = Vec::new();
+    pv.write_to(&mut bytes, LeBytes).unwrap();
+    let read: PaletteVec<u32, P, B> = PaletteVec::read_from(bytes.as_slice(), LeBytes).unwrap();
+    assert!(read.iter().eq(control.iter()));
+    assert_eq!(read.unique_values(), pv.unique_values());
+    // The format doesn't depend on the backend
+    let read: PaletteVec<u32, VecPalette<u32>, FastIndexBuffer> =
+        PaletteVec::read_from(bytes.as_slice(), LeBytes).unwrap();
+    assert!(read.iter().eq(control.iter()));
+    let mut rewritten = Vec::new();
+    read.write_to(&mut rewritten, LeBytes).unwrap();
+    assert_eq!(rewritten, bytes);
+
+    // Corrupted data must never panic
+    for _ in 0..16 {
+        let mut corrupted = bytes.clone();
+        for _ in 0..rng.random_range(1..4) {
+            let offset = rng.random_range(0..corrupted.len());
+            corrupted[offset] = rng.random();
+        }
+        let _ = PaletteVec::<u32, P, B>::read_from(corrupted.as_slice(), LeBytes);
+    }
+
+    // Hand written: [7, 8, 7] with 1 bit per index
+    let mut expected = Vec::new();
+    expected.extend_from_slice(&MAGIC);
+    expected.extend_from_slice(&VERSION.to_le_bytes());
+    expected.extend_from_slice(&3u64.to_le_bytes());
+    expected.push(1);
+    expected.extend_from_slice(&2u64.to_le_bytes());
+    expected.extend_from_slice(&7u32.to_le_bytes());
+    expected.extend_from_slice(&8u32.to_le_bytes());
+    expected.extend_from_slice(&0b010u64.to_le_bytes());
+    let small: PaletteVec<u32, P, B> = [7, 8, 7].as_slice().into();
+    let mut bytes = Vec::new();
+    small.write_to(&mut bytes, LeBytes).unwrap();
+    assert_eq!(bytes, expected);
+    let empty: PaletteVec<u32, P, B> = PaletteVec::new();
+    let mut bytes = Vec::new();
+    empty.write_to(&mut bytes, LeBytes).unwrap();
+    assert!(PaletteVec::<u32, P, B>::read_from(bytes.as_slice(), LeBytes)
+        .unwrap()
+        .is_empty());
+
+    let error_kind = |bytes: &[u8]| {
+        PaletteVec::<u32, P, B>::read_from(bytes, LeBytes)
+            .err()
+            .map(|err| err.kind())
+    };
+    assert_eq!(error_kind(&expected), None);
+    let mut corrupted = expected.clone();
+    corrupted[0] = b'X';
+    assert_eq!(error_kind(&corrupted), Some(ErrorKind::InvalidData));
+    let mut corrupted = expected.clone();
+    corrupted[4] = 2;
+    assert_eq!(error_kind(&corrupted), Some(ErrorKind::InvalidData));
+    let mut corrupted = expected.clone();
+    corrupted[14] = 65;
+    assert_eq!(error_kind(&corrupted), Some(ErrorKind::InvalidData));
+    // 3 palette values don't fit into 1 bit
+    let mut corrupted = expected.clone();
+    corrupted[15] = 3;
+    assert_eq!(error_kind(&corrupted), Some(ErrorKind::InvalidData));
+    // 2 bits per index, but index 3 doesn't exist
+    let mut corrupted = expected.clone();
+    corrupted[14] = 2;
+    corrupted[31..39].copy_from_slice(&0b11_00_00u64.to_le_bytes());
+    assert_eq!(error_kind(&corrupted), Some(ErrorKind::InvalidData));
+    assert_eq!(
+        error_kind(&expected[..expected.len() - 1]),
+        Some(ErrorKind::InvalidData)
+    );
+    assert_eq!(error_kind(&expected[..10]), Some(ErrorKind::UnexpectedEof));
+}
+
+fn test_palette_vec_ref<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    use crate::{view::PaletteVecRef, InvariantError};
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let amount_unique_values = rng.random_range(1..300);
+    let mut control = Vec::new();
+    for _ in 0..rng.random_range(0..iteration_count) {
+        control.push(rng.random_range(0..amount_unique_values));
+    }
+    let mut pv: PaletteVec<u32, P, B> = control.as_slice().into();
+    // Leave holes in the palette and garbage behind the last index
+    for _ in 0..rng.random_range(0..20) {
+        pv.push(amount_unique_values + rng.random_range(0..100));
+    }
+    pv.truncate(control.len());
+
+    let min_bits = rng.random_range(0..=64);
+    let packed = pv.to_packed_longs(min_bits);
+    let view = PaletteVecRef::new(&packed.palette, &packed.longs, packed.bits, control.len()).unwrap();
+    assert_eq!(view.len(), control.len());
+    assert_eq!(view.is_empty(), control.is_empty());
+    assert!(view.iter().eq(control.iter()));
+    assert!(view.iter().rev().eq(control.iter().rev()));
+    assert_eq!(view.iter().len(), control.len());
+    for (offset, value) in control.iter().enumerate() {
+        assert_eq!(view.get(offset), Some(value));
+    }
+    assert_eq!(view.get(control.len()), None);
+    for _ in 0..10 {
+        let value = rng.random_range(0..amount_unique_values + 10);
+        let count = control.iter().filter(|other| **other == value).count();
+        assert_eq!(view.count_of(&value), count);
+    }
+
+    let mut runs: Vec<(&u32, usize)> = Vec::new();
+    for value in control.iter() {
+        match runs.last_mut() {
+            Some((last, length)) if *last == value => *length += 1,
+            _ => runs.push((value, 1)),
+        }
+    }
+    assert_eq!(view.iter_runs().collect::<Vec<_>>(), runs);
+    assert!(view.iter_runs().rev().eq(runs.iter().rev().copied()));
+
+    let owned: PaletteVec<u32, P, B> = view.to_owned();
+    assert!(owned.iter().eq(control.iter()));
+    assert_eq!(owned.unique_values(), pv.unique_values());
+    assert_eq!(owned.check_invariants(), Ok(()));
+
+    // Duplicate palette values are merged in runs, counts and the owned copy
+    let palette = [7, 3, 7];
+    let words = [0b10_01_00_10];
+    let view = PaletteVecRef::new(&palette, &words, 2, 4).unwrap();
+    assert_eq!(view.iter_runs().collect::<Vec<_>>(), [(&7, 2), (&3, 1), (&7, 1)]);
+    assert_eq!(view.count_of(&7), 3);
+    let owned: PaletteVec<u32, P, B> = view.to_owned();
+    assert_eq!(owned.unique_values(), 2);
+
+    assert_eq!(
+        PaletteVecRef::new(&[1u32], &[], 65, 1).err(),
+        Some(InvariantError::UnsupportedIndexSize(65))
+    );
+    assert_eq!(
+        PaletteVecRef::new(&[1u32], &[0], 8, 9).err(),
+        Some(InvariantError::StorageTooShort {
+            expected: 2,
+            found: 1
+        })
+    );
+    assert_eq!(
+        PaletteVecRef::new(&[1u32, 2], &[0x0200], 8, 2).err(),
+        Some(InvariantError::MissingEntry {
+            offset: 1,
+            index: 2
+        })
+    );
+    assert_eq!(
+        PaletteVecRef::<u32>::new(&[], &[], 0, 3).err(),
+        Some(InvariantError::MissingEntry {
+            offset: 0,
+            index: 0
+        })
+    );
+    let view = PaletteVecRef::new(&[5u32], &[], 0, 3).unwrap();
+    assert!(view.iter().eq([5, 5, 5].iter()));
+    assert_eq!(view.iter_runs().collect::<Vec<_>>(), [(&5, 3)]);
+}
+
+fn test_palette_vec_convert<P, B>(seed: u64, iteration_count: usize)
+where
+    P: Palette<u32>,
+    B: IndexBuffer,
+{
+    use crate::{
+        index_buffer::{aligned::AlignedIndexBuffer, fast::FastIndexBuffer},
+        palette::hybrid::HybridPalette,
+    };
+
+    fn check<P, B, P2, B2>(pv: &PaletteVec<u32, P, B>, control: &[u32])
+    where
+        P: Palette<u32>,
+        B: IndexBuffer,
+        P2: Palette<u32>,
+        B2: IndexBuffer,
+    {
+        let mut converted: PaletteVec<u32, P2, B2> = pv.convert();
+        assert_eq!(converted.check_invariants(), Ok(()));
+        assert!(converted.iter().eq(control.iter()));
+        assert_eq!(converted.unique_values(), pv.unique_values());
+        // Every entry keeps its index, holes included
+        for (index, entry) in pv.palette_entries_by_index() {
+            assert!(converted.palette.get_by_index(index) == Some(entry));
+        }
+        // Still fully usable afterwards
+        converted.push(7);
+        converted.set(0, &3);
+        let mut control = control.to_vec();
+        control.push(7);
+        control[0] = 3;
+        assert!(converted.iter().eq(control.iter()));
+        assert_eq!(converted.check_invariants(), Ok(()));
+    }
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let amount_unique_values = rng.random_range(1..300);
+    let mut control = Vec::new();
+    for _ in 0..rng.random_range(0..iteration_count) {
+        control.push(rng.random_range(0..amount_unique_values));
+    }
+    let mut pv: PaletteVec<u32, P, B> = control.as_slice().into();
+    // Leave holes in the palette and garbage behind the last index
+    for _ in 0..rng.random_range(0..20) {
+        pv.push(amount_unique_values + rng.random_range(0..100));
+    }
+    pv.truncate(control.len());
+    // Free an index in the middle of the palette
+    if control.len() > 1 {
+        let (old, new) = (control[0], control[control.len() - 1]);
+        pv.replace_all(&old, new);
+        control.iter_mut().filter(|value| **value == old).for_each(|value| *value = new);
+    }
+    if rng.random_bool(0.5) {
+        pv.optimize();
+    }
+
+    if control.is_empty() {
+        control.push(1);
+        pv.push(1);
+    }
+    check::<P, B, P, B>(&pv, &control);
+    check::<P, B, VecPalette<u32>, FastIndexBuffer>(&pv, &control);
+    check::<P, B, VecPalette<u32>, AlignedIndexBuffer>(&pv, &control);
+    check::<P, B, HybridPalette<4, u32>, AlignedIndexBuffer>(&pv, &control);
+    check::<P, B, HybridPalette<199, u32>, FastIndexBuffer>(&pv, &control);
+}
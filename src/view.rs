@@ -0,0 +1,293 @@
+//! Read-only views over packed indices that live outside of a `PaletteVec`,
+//! for example in a memory-mapped file.
+//!
+//! The words use the layout of the index buffers: `64 / index_size` indices per
+//! u64, starting at the least significant bits, never crossing a word boundary.
+use std::{hash::Hash, iter::FusedIterator};
+
+use crate::{
+    index_buffer::{packed, IndexBuffer},
+    palette::Palette,
+    InvariantError, PaletteVec,
+};
+
+/// A borrowed, read-only `PaletteVec` made of a dense palette and packed indices into it.
+///
+/// Nothing is copied, the indices are decoded on access.
+#[derive(Debug)]
+pub struct PaletteVecRef<'a, T> {
+    palette: &'a [T],
+    words: &'a [u64],
+    index_size: usize,
+    len: usize,
+}
+
+// Derives would require `T: Copy`
+impl<T> Clone for PaletteVecRef<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for PaletteVecRef<'_, T> {}
+
+impl<'a, T: Eq + Hash + Clone> PaletteVecRef<'a, T> {
+    /// Creates a view over `len` indices of `index_size` bits, stored in `words`.
+    /// With an index size of 0, every element is `palette[0]`.
+    ///
+    /// Checks that `words` is long enough and that every index points into the
+    /// palette, so all accesses afterwards are infallible.
+    pub fn new(
+        palette: &'a [T],
+        words: &'a [u64],
+        index_size: usize,
+        len: usize,
+    ) -> Result<Self, InvariantError> {
+        if index_size > 64 {
+            return Err(InvariantError::UnsupportedIndexSize(index_size));
+        }
+        match 64usize.checked_div(index_size) {
+            None if len > 0 && palette.is_empty() => {
+                return Err(InvariantError::MissingEntry {
+                    offset: 0,
+                    index: 0,
+                });
+            }
+            None => {}
+            Some(indices_per_u64) => {
+                let expected = len.div_ceil(indices_per_u64);
+                if words.len() < expected {
+                    return Err(InvariantError::StorageTooShort {
+                        expected,
+                        found: words.len(),
+                    });
+                }
+                let mut offset = 0;
+                let mut missing = None;
+                packed::for_each(words, index_size, len, |index| {
+                    if missing.is_none() && index >= palette.len() {
+                        missing = Some(InvariantError::MissingEntry { offset, index });
+                    }
+                    offset += 1;
+                });
+                if let Some(missing) = missing {
+                    return Err(missing);
+                }
+            }
+        }
+        Ok(Self {
+            palette,
+            words,
+            index_size,
+            len,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn index_size(&self) -> usize {
+        self.index_size
+    }
+
+    pub fn palette(&self) -> &'a [T] {
+        self.palette
+    }
+
+    pub fn words(&self) -> &'a [u64] {
+        self.words
+    }
+
+    fn index_at(&self, offset: usize) -> usize {
+        if self.index_size == 0 {
+            return 0;
+        }
+        packed::get(self.words, self.index_size, offset)
+    }
+
+    pub fn get(&self, offset: usize) -> Option<&'a T> {
+        if offset >= self.len {
+            return None;
+        }
+        Some(&self.palette[self.index_at(offset)])
+    }
+
+    pub fn iter(&self) -> PaletteVecRefIter<'a, T> {
+        PaletteVecRefIter {
+            view: *self,
+            offset: 0,
+            end: self.len,
+        }
+    }
+
+    /// Returns an iterator over runs of equal elements, as `(value, run_length)`.
+    /// See [`PaletteVec::iter_runs`].
+    pub fn iter_runs(&self) -> PaletteVecRefRuns<'a, T> {
+        PaletteVecRefRuns {
+            view: *self,
+            offset: 0,
+            end: self.len,
+        }
+    }
+
+    /// Returns how often `value` occurs. The palette may contain `value` more than once.
+    pub fn count_of(&self, value: &T) -> usize {
+        let matches = self
+            .palette
+            .iter()
+            .map(|other| other == value)
+            .collect::<Vec<_>>();
+        if !matches.contains(&true) {
+            return 0;
+        }
+        if self.index_size == 0 {
+            return self.len;
+        }
+        let mut count = 0;
+        packed::for_each(self.words, self.index_size, self.len, |index| {
+            count += matches[index] as usize;
+        });
+        count
+    }
+
+    /// Copies the elements into a `PaletteVec`. Unused palette values are skipped
+    /// and duplicate ones merged.
+    pub fn to_owned<P: Palette<T>, B: IndexBuffer>(&self) -> PaletteVec<T, P, B> {
+        if self.index_size == 0 {
+            return match self.palette.first() {
+                Some(value) => PaletteVec::filled(value.clone(), self.len),
+                None => PaletteVec::new(),
+            };
+        }
+        let mut indices = Vec::with_capacity(self.len);
+        packed::for_each(self.words, self.index_size, self.len, |index| {
+            indices.push(index)
+        });
+        PaletteVec::from_dense_parts(self.palette, indices)
+    }
+}
+
+impl<'a, T: Eq + Hash + Clone> IntoIterator for &PaletteVecRef<'a, T> {
+    type Item = &'a T;
+    type IntoIter = PaletteVecRefIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// ITERATOR
+#[derive(Debug, Clone)]
+pub struct PaletteVecRefIter<'a, T> {
+    view: PaletteVecRef<'a, T>,
+    offset: usize,
+    end: usize,
+}
+
+impl<'a, T: Eq + Hash + Clone> Iterator for PaletteVecRefIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.end {
+            return None;
+        }
+        self.offset += 1;
+        self.view.get(self.offset - 1)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.offset;
+        (remaining, Some(remaining))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.offset = self.offset.saturating_add(n).min(self.end);
+        self.next()
+    }
+}
+
+impl<T: Eq + Hash + Clone> DoubleEndedIterator for PaletteVecRefIter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.end {
+            return None;
+        }
+        self.end -= 1;
+        self.view.get(self.end)
+    }
+}
+
+impl<T: Eq + Hash + Clone> ExactSizeIterator for PaletteVecRefIter<'_, T> {}
+
+impl<T: Eq + Hash + Clone> FusedIterator for PaletteVecRefIter<'_, T> {}
+
+// RUNS ITERATOR
+#[derive(Debug, Clone)]
+pub struct PaletteVecRefRuns<'a, T> {
+    view: PaletteVecRef<'a, T>,
+    offset: usize,
+    end: usize,
+}
+
+impl<'a, T: Eq + Hash + Clone> PaletteVecRefRuns<'a, T> {
+    /// Whether the elements at both offsets are equal. Duplicate palette
+    /// values count as equal.
+    fn same_value(&self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.view.index_at(a), self.view.index_at(b));
+        a == b || self.view.palette[a] == self.view.palette[b]
+    }
+}
+
+impl<'a, T: Eq + Hash + Clone> Iterator for PaletteVecRefRuns<'a, T> {
+    type Item = (&'a T, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.end {
+            return None;
+        }
+        let start = self.offset;
+        self.offset = if self.view.index_size == 0 {
+            self.end
+        } else {
+            (start + 1..self.end)
+                .find(|offset| !self.same_value(start, *offset))
+                .unwrap_or(self.end)
+        };
+        Some((
+            &self.view.palette[self.view.index_at(start)],
+            self.offset - start,
+        ))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.offset;
+        (remaining.min(1), Some(remaining))
+    }
+}
+
+impl<T: Eq + Hash + Clone> DoubleEndedIterator for PaletteVecRefRuns<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.end {
+            return None;
+        }
+        let last = self.end - 1;
+        self.end = if self.view.index_size == 0 {
+            self.offset
+        } else {
+            (self.offset..last)
+                .rev()
+                .find(|offset| !self.same_value(last, *offset))
+                .map_or(self.offset, |offset| offset + 1)
+        };
+        Some((
+            &self.view.palette[self.view.index_at(last)],
+            last + 1 - self.end,
+        ))
+    }
+}
+
+impl<T: Eq + Hash + Clone> FusedIterator for PaletteVecRefRuns<'_, T> {}